@@ -1,4 +1,10 @@
-use ndarray::{Array2, Ix2};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+use ndarray::{s, Array2, Axis, Ix2, Ix3};
 use ort::Session;
 
 use crate::errors::Sbv2CoreError;
@@ -15,10 +21,289 @@ pub fn predict(
         }?
     )?;
 
-    let output = outputs["output"]
-        .try_extract_tensor::<f32>()?
-        .into_dimensionality::<Ix2>()?
-        .to_owned();
+    let output = outputs["output"].try_extract_tensor::<f32>()?;
+
+    // バッチの次元がある場合は取り除く
+    let output = match output.ndim() {
+        3 => output.index_axis(Axis(0), 0).into_dimensionality::<Ix2>()?,
+        _ => output.into_dimensionality::<Ix2>()?,
+    };
+
+    Ok(output.to_owned())
+}
+
+/// 複数の文をパディングして一度に推論し、文ごとの特徴量を返す
+///
+/// 出力にバッチの次元がある (`[batch, tokens, hidden]`) BERT のみバッチで推論する。
+/// Style-Bert-VITS2 の標準の export はグラフ内で 1 文目だけを取り出して `[tokens, hidden]` を返すため、
+/// パディングしても 2 文目以降の特徴量が得られず、一文ずつ推論する
+pub fn predict_batch(
+    session: &Session,
+    inputs: Vec<(Vec<i64>, Vec<i64>)>,
+) -> Result<Vec<Array2<f32>>, Sbv2CoreError> {
+    if inputs.len() <= 1 || !crate::model::bert_supports_batch(session) {
+        return inputs
+            .into_iter()
+            .map(|(token_ids, attention_masks)| predict(session, token_ids, attention_masks))
+            .collect();
+    }
+
+    let mut results = vec![];
+
+    for chunk in inputs.chunks(BATCH_SIZE) {
+        let max_len = chunk.iter().map(|(ids, _)| ids.len()).max().unwrap_or(0);
+
+        // パディングした部分は attention_mask を 0 にする
+        let mut token_ids = Array2::<i64>::zeros((chunk.len(), max_len));
+        let mut attention_masks = Array2::<i64>::zeros((chunk.len(), max_len));
+        for (i, (ids, masks)) in chunk.iter().enumerate() {
+            for (j, (id, mask)) in ids.iter().zip(masks).enumerate() {
+                token_ids[[i, j]] = *id;
+                attention_masks[[i, j]] = *mask;
+            }
+        }
+
+        let outputs = session.run(ort::inputs! {
+            "input_ids" => token_ids,
+            "attention_mask" => attention_masks,
+        }?)?;
+
+        let output = outputs["output"]
+            .try_extract_tensor::<f32>()?
+            .into_dimensionality::<Ix3>()?;
+
+        for (i, (ids, _)) in chunk.iter().enumerate() {
+            results.push(output.slice(s![i, ..ids.len(), ..]).to_owned());
+        }
+    }
+
+    Ok(results)
+}
+
+// 一度に推論する文の数の上限
+const BATCH_SIZE: usize = 8;
+
+// ほかのスレッドの入力を待つ時間
+const BATCH_WAIT: Duration = Duration::from_millis(2);
+
+/// 複数のスレッドから同時に求められた BERT の推論を、session ごとにまとめてバッチで推論する
+///
+/// 最初に入力を追加したスレッドが `BATCH_WAIT` の間ほかのスレッドの入力を待ち、
+/// それまでに追加された同じ session の入力をまとめて推論して、結果をそれぞれのスレッドに返す
+#[derive(Default)]
+pub struct BertBatcher {
+    state: Mutex<BatcherState>,
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct BatcherState {
+    next_request: u64,
+    pending: Vec<PendingInput>,
+    // 要求ごとの (要求の中の番号, 結果)
+    done: HashMap<u64, Vec<(usize, Result<Array2<f32>, String>)>>,
+    // 推論中の session
+    running: HashSet<usize>,
+}
+
+struct PendingInput {
+    session: usize,
+    request: u64,
+    index: usize,
+    input: (Vec<i64>, Vec<i64>),
+}
+
+impl BertBatcher {
+    pub fn predict(
+        &self,
+        session: &Session,
+        token_ids: Vec<i64>,
+        attention_masks: Vec<i64>,
+    ) -> Result<Array2<f32>, Sbv2CoreError> {
+        let mut outputs = self.predict_batch(session, vec![(token_ids, attention_masks)])?;
+        Ok(outputs.remove(0))
+    }
+
+    /// `predict_batch` と同じだが、ほかのスレッドの入力とまとめて推論する
+    ///
+    /// 出力にバッチの次元がない BERT はまとめられないので、それぞれのスレッドで推論する
+    pub fn predict_batch(
+        &self,
+        session: &Session,
+        inputs: Vec<(Vec<i64>, Vec<i64>)>,
+    ) -> Result<Vec<Array2<f32>>, Sbv2CoreError> {
+        if !crate::model::bert_supports_batch(session) {
+            return predict_batch(session, inputs);
+        }
+
+        self.run_batched(session as *const Session as usize, inputs, |inputs| {
+            predict_batch(session, inputs)
+        })
+    }
+
+    fn run_batched(
+        &self,
+        session: usize,
+        inputs: Vec<(Vec<i64>, Vec<i64>)>,
+        run: impl Fn(Vec<(Vec<i64>, Vec<i64>)>) -> Result<Vec<Array2<f32>>, Sbv2CoreError>,
+    ) -> Result<Vec<Array2<f32>>, Sbv2CoreError> {
+        let count = inputs.len();
+        if count == 0 {
+            return Ok(vec![]);
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let request = state.next_request;
+        state.next_request += 1;
+        state.pending.extend(
+            inputs
+                .into_iter()
+                .enumerate()
+                .map(|(index, input)| PendingInput {
+                    session,
+                    request,
+                    index,
+                    input,
+                }),
+        );
+        self.condvar.notify_all();
+
+        loop {
+            if state
+                .done
+                .get(&request)
+                .is_some_and(|done| done.len() == count)
+            {
+                let mut done = state.done.remove(&request).unwrap();
+                done.sort_by_key(|(index, _)| *index);
+
+                return done
+                    .into_iter()
+                    .map(|(_, output)| output.map_err(Sbv2CoreError::ValueError))
+                    .collect();
+            }
+
+            // この session を推論しているスレッドがなければ、入力をまとめて推論する
+            if state.running.insert(session) {
+                let deadline = Instant::now() + BATCH_WAIT;
+                loop {
+                    let pending = state.pending.iter().filter(|p| p.session == session);
+                    let now = Instant::now();
+                    if pending.count() >= BATCH_SIZE || now >= deadline {
+                        break;
+                    }
+
+                    state = self.condvar.wait_timeout(state, deadline - now).unwrap().0;
+                }
+
+                let (batch, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut state.pending)
+                    .into_iter()
+                    .partition(|p| p.session == session);
+                state.pending = rest;
+                drop(state);
+
+                let (ids, inputs): (Vec<_>, Vec<_>) = batch
+                    .into_iter()
+                    .map(|p| ((p.request, p.index), p.input))
+                    .unzip();
+                let outputs = run(inputs);
+
+                state = self.state.lock().unwrap();
+                state.running.remove(&session);
+                match outputs {
+                    Ok(outputs) => {
+                        for ((request, index), output) in ids.into_iter().zip(outputs) {
+                            state
+                                .done
+                                .entry(request)
+                                .or_default()
+                                .push((index, Ok(output)));
+                        }
+                    }
+                    // まとめて推論したすべての要求にエラーを返す
+                    Err(err) => {
+                        let message = format!("batched BERT inference failed: {}", err);
+                        for (request, index) in ids {
+                            state
+                                .done
+                                .entry(request)
+                                .or_default()
+                                .push((index, Err(message.clone())));
+                        }
+                    }
+                }
+                self.condvar.notify_all();
+                continue;
+            }
+
+            state = self.condvar.wait(state).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Barrier};
+
+    use super::*;
+
+    // 入力の最初のトークンを特徴量にし、推論したバッチの大きさを記録する
+    fn fake_run(
+        batches: &Mutex<Vec<usize>>,
+    ) -> impl Fn(Vec<(Vec<i64>, Vec<i64>)>) -> Result<Vec<Array2<f32>>, Sbv2CoreError> + '_ {
+        move |inputs| {
+            batches.lock().unwrap().push(inputs.len());
+            std::thread::sleep(Duration::from_millis(20));
+
+            Ok(inputs
+                .iter()
+                .map(|(ids, _)| Array2::from_elem((ids.len(), 2), ids[0] as f32))
+                .collect())
+        }
+    }
+
+    #[test]
+    fn run_batched_groups_concurrent_requests() {
+        let batcher = BertBatcher::default();
+        let batches = Mutex::new(vec![]);
+        let barrier = Arc::new(Barrier::new(4));
+
+        std::thread::scope(|scope| {
+            for t in 0..4 {
+                let (batcher, batches, barrier) = (&batcher, &batches, barrier.clone());
+
+                scope.spawn(move || {
+                    let inputs = vec![(vec![t * 10, 0], vec![1, 1]), (vec![t * 10 + 1], vec![1])];
+                    barrier.wait();
+
+                    let outputs = batcher.run_batched(0, inputs, fake_run(batches)).unwrap();
+                    // 要求ごとに入力の順に結果が返る
+                    assert_eq!(outputs.len(), 2);
+                    assert_eq!(outputs[0].shape(), [2, 2]);
+                    assert_eq!(outputs[0][[0, 0]], (t * 10) as f32);
+                    assert_eq!(outputs[1][[0, 0]], (t * 10 + 1) as f32);
+                });
+            }
+        });
+
+        let batches = batches.into_inner().unwrap();
+        assert_eq!(batches.iter().sum::<usize>(), 8);
+        assert!(batches.len() < 4, "{:?}", batches);
+    }
+
+    #[test]
+    fn run_batched_returns_errors_to_every_request() {
+        let batcher = BertBatcher::default();
+        let outputs = batcher.run_batched(0, vec![(vec![1], vec![1]), (vec![2], vec![1])], |_| {
+            Err(Sbv2CoreError::ValueError("failed".to_string()))
+        });
+        assert!(outputs.unwrap_err().to_string().contains("failed"));
 
-    Ok(output)
+        // 失敗した後も使える
+        let batches = Mutex::new(vec![]);
+        let outputs = batcher
+            .run_batched(0, vec![(vec![3], vec![1])], fake_run(&batches))
+            .unwrap();
+        assert_eq!(outputs[0][[0, 0]], 3.0);
+    }
 }
@@ -0,0 +1,451 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use regex::{Captures, Regex};
+
+use crate::{errors::Sbv2CoreError, norm::PUNCTUATIONS};
+
+// `字 zi4`, `银行 yin2 hang2` の形式の辞書
+// CJK 統合漢字の単字の読み (CLDR の Han-Latin 変換から生成) と頻出語の読みを含む
+static PINYIN_DICT: LazyLock<HashMap<String, Vec<String>>> =
+    LazyLock::new(|| parse_pinyin_dict(include_str!("./pinyin.dict")));
+static PINYIN_DICT_MAX_LEN: LazyLock<usize> = LazyLock::new(|| {
+    PINYIN_DICT
+        .keys()
+        .map(|k| k.chars().count())
+        .max()
+        .unwrap_or(1)
+});
+
+static NUMBER_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[0-9]+(?:,[0-9]{3})*(?:\.[0-9]+)?").unwrap());
+
+const DIGITS: [&str; 10] = ["零", "一", "二", "三", "四", "五", "六", "七", "八", "九"];
+
+// 長いものから順に並べる
+const INITIALS: [&str; 23] = [
+    "zh", "ch", "sh", "b", "p", "m", "f", "d", "t", "n", "l", "g", "k", "h", "j", "q", "x", "r",
+    "z", "c", "s", "y", "w",
+];
+
+pub(crate) struct ChineseG2p {
+    user_dict: HashMap<String, Vec<String>>,
+}
+
+impl ChineseG2p {
+    pub fn new() -> Self {
+        ChineseG2p {
+            user_dict: HashMap::new(),
+        }
+    }
+
+    /// 拼音辞書を読み込み、同梱の辞書より優先して使う
+    pub fn load_pinyin_dict(&mut self, pinyin_dict: &str) -> Result<(), Sbv2CoreError> {
+        let dict = parse_pinyin_dict(pinyin_dict);
+
+        if dict.is_empty() {
+            return Err(Sbv2CoreError::ValueError(
+                "pinyin dict has no entries".to_string(),
+            ));
+        }
+
+        self.user_dict.extend(dict);
+        Ok(())
+    }
+
+    /// `text` は `normalize_text` で正規化したもの (漢字と句読点のみ)
+    ///
+    /// 辞書に読みがない文字がある場合はエラーにする
+    pub fn g2p(&self, text: &str) -> Result<(Vec<String>, Vec<i32>, Vec<i32>), Sbv2CoreError> {
+        let syllables = self.text_to_pinyin(text);
+
+        let missing: Vec<String> = text
+            .chars()
+            .zip(&syllables)
+            .filter(|(c, syllable)| {
+                syllable.is_none() && !PUNCTUATIONS.contains(&c.to_string().as_str())
+            })
+            .map(|(c, _)| c.to_string())
+            .collect();
+        if !missing.is_empty() {
+            return Err(Sbv2CoreError::ValueError(format!(
+                "no pinyin reading for: {} (load a larger dictionary with `load_pinyin_dict`)",
+                missing.join(", ")
+            )));
+        }
+
+        let mut phones = vec!["_".to_string()];
+        let mut tones = vec![0];
+        let mut word2ph = vec![1];
+
+        for (c, syllable) in text.chars().zip(syllables) {
+            let c = c.to_string();
+
+            if PUNCTUATIONS.contains(&c.as_str()) {
+                phones.push(c);
+                tones.push(0);
+                word2ph.push(1);
+                continue;
+            }
+
+            let Some((pinyin, tone)) = syllable else {
+                unreachable!("checked above");
+            };
+
+            let (initial, final_) = pinyin_to_symbols(&pinyin);
+            phones.push(initial);
+            phones.push(final_);
+            tones.extend([tone, tone]);
+            word2ph.push(2);
+        }
+
+        phones.push("_".to_string());
+        tones.push(0);
+        word2ph.push(1);
+
+        Ok((phones, tones, word2ph))
+    }
+
+    fn lookup(&self, word: &str) -> Option<&Vec<String>> {
+        self.user_dict.get(word).or_else(|| PINYIN_DICT.get(word))
+    }
+
+    // 最長一致で単語に分割し、一文字ごとの (拼音, 声調) を返す
+    fn text_to_pinyin(&self, text: &str) -> Vec<Option<(String, i32)>> {
+        let chars: Vec<char> = text.chars().collect();
+        let max_len = self
+            .user_dict
+            .keys()
+            .map(|k| k.chars().count())
+            .fold(*PINYIN_DICT_MAX_LEN, usize::max);
+
+        let mut syllables = vec![];
+        let mut single_chars = vec![];
+
+        let mut i = 0;
+        while i < chars.len() {
+            let found = (1..=max_len.min(chars.len() - i)).rev().find_map(|len| {
+                let word: String = chars[i..i + len].iter().collect();
+                self.lookup(&word)
+                    .filter(|pinyins| pinyins.len() == len)
+                    .map(|pinyins| (len, pinyins))
+            });
+
+            match found {
+                Some((len, pinyins)) => {
+                    for pinyin in pinyins {
+                        syllables.push(split_tone(pinyin));
+                        single_chars.push(len == 1);
+                    }
+                    i += len;
+                }
+                None => {
+                    syllables.push(None);
+                    single_chars.push(true);
+                    i += 1;
+                }
+            }
+        }
+
+        tone_sandhi(&chars, &mut syllables, &single_chars);
+
+        syllables
+    }
+}
+
+fn parse_pinyin_dict(pinyin_dict: &str) -> HashMap<String, Vec<String>> {
+    let mut map = HashMap::new();
+
+    for line in pinyin_dict.lines() {
+        let mut parts = line.split_whitespace();
+
+        let Some(word) = parts.next() else {
+            continue;
+        };
+        if word.starts_with('#') {
+            continue;
+        }
+
+        let pinyins: Vec<String> = parts.map(|x| x.to_string()).collect();
+        if !pinyins.is_empty() {
+            map.insert(word.to_string(), pinyins);
+        }
+    }
+
+    map
+}
+
+// `hao3` → (`hao`, 3), 声調の数字がない場合は軽声 (5)
+fn split_tone(pinyin: &str) -> Option<(String, i32)> {
+    let pinyin = pinyin.to_lowercase().replace('ü', "v");
+
+    match pinyin.chars().last()?.to_digit(10) {
+        Some(tone @ 1..=5) => Some((pinyin[..pinyin.len() - 1].to_string(), tone as i32)),
+        Some(_) => None,
+        None => Some((pinyin, 5)),
+    }
+}
+
+fn tone_sandhi(chars: &[char], syllables: &mut [Option<(String, i32)>], single_chars: &[bool]) {
+    let tone_at = |syllables: &[Option<(String, i32)>], i: usize| {
+        syllables
+            .get(i)
+            .and_then(|s| s.as_ref())
+            .map(|(_, tone)| *tone)
+    };
+
+    // 不, 一 の声調変化 (辞書の単語に含まれるものは辞書の声調を使う)
+    for i in 0..syllables.len() {
+        if !single_chars[i] {
+            continue;
+        }
+
+        let next_tone = tone_at(syllables, i + 1);
+        let Some((pinyin, tone)) = syllables[i].as_mut() else {
+            continue;
+        };
+
+        match (chars[i], next_tone) {
+            ('不', Some(4)) => *tone = 2,
+            ('一', Some(4)) if pinyin == "yi" => *tone = 2,
+            ('一', Some(1..=3)) if pinyin == "yi" => *tone = 4,
+            _ => (),
+        }
+    }
+
+    // 第三声が続く場合は前を第二声にする
+    for i in 0..syllables.len().saturating_sub(1) {
+        if tone_at(syllables, i) == Some(3) && tone_at(syllables, i + 1) == Some(3) {
+            if let Some((_, tone)) = syllables[i].as_mut() {
+                *tone = 2;
+            }
+        }
+    }
+}
+
+/// 拼音 (声調なし) を ZH_SYMBOLS の声母と韻母に分ける
+fn pinyin_to_symbols(pinyin: &str) -> (String, String) {
+    let (initial, rest) = match INITIALS.iter().find(|i| pinyin.starts_with(*i)) {
+        Some(initial) => (*initial, &pinyin[initial.len()..]),
+        None => ("", pinyin),
+    };
+
+    let final_ = match (initial, rest) {
+        // 声母のない音節
+        ("", _) => {
+            let initial = match rest.chars().next() {
+                Some('a') => "AA",
+                Some('e') => "EE",
+                _ => "OO",
+            };
+            return (initial.to_string(), rest.to_string());
+        }
+
+        ("y", "a") => "ia",
+        ("y", "an") => "En",
+        ("y", "ang") => "iang",
+        ("y", "ao") => "iao",
+        ("y", "e") => "E",
+        ("y", "ong") => "iong",
+        ("y", "ou") => "iu",
+        ("y", "u") => "v",
+        ("y", "uan") => "van",
+        ("y", "ue") => "ve",
+        ("y", "un") => "vn",
+
+        ("w", "a") => "ua",
+        ("w", "ai") => "uai",
+        ("w", "an") => "uan",
+        ("w", "ang") => "uang",
+        ("w", "ei") => "ui",
+        ("w", "en") => "un",
+        ("w", "o") => "uo",
+
+        ("j" | "q" | "x", "u") => "v",
+        ("j" | "q" | "x", "uan") => "van",
+        ("j" | "q" | "x", "ue") => "ve",
+        ("j" | "q" | "x", "un") => "vn",
+
+        ("zh" | "ch" | "sh" | "r", "i") => "ir",
+        ("z" | "c" | "s", "i") => "i0",
+
+        (_, "iou") => "iu",
+        (_, "uei") => "ui",
+        (_, "uen") => "un",
+
+        (_, rest) => rest,
+    };
+
+    (initial.to_string(), final_.to_string())
+}
+
+/// 中国語のテキストを G2P の前に正規化する
+pub fn normalize_text(text: &str) -> String {
+    let text = NUMBER_PATTERN
+        .replace_all(text, |caps: &Captures| number_to_chinese(&caps[0]))
+        .to_string();
+
+    crate::norm::replace_punctuation_zh(text)
+}
+
+fn number_to_chinese(number: &str) -> String {
+    let number = number.replace(',', "");
+    let (integer, decimal) = match number.split_once('.') {
+        Some((integer, decimal)) => (integer, Some(decimal)),
+        None => (number.as_str(), None),
+    };
+
+    let mut result = match integer.parse::<u64>() {
+        Ok(n) => integer_to_chinese(n),
+        Err(_) => digits_to_chinese(integer),
+    };
+
+    if let Some(decimal) = decimal {
+        result += "点";
+        result += &digits_to_chinese(decimal);
+    }
+
+    result
+}
+
+fn digits_to_chinese(digits: &str) -> String {
+    digits
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|d| DIGITS[d as usize])
+        .collect()
+}
+
+fn integer_to_chinese(mut n: u64) -> String {
+    if n == 0 {
+        return DIGITS[0].to_string();
+    }
+
+    const LARGE_UNITS: [&str; 5] = ["", "万", "亿", "万亿", "亿亿"];
+    const SMALL_UNITS: [&str; 4] = ["", "十", "百", "千"];
+
+    let mut groups = vec![];
+    while n > 0 {
+        groups.push(n % 10000);
+        n /= 10000;
+    }
+
+    let mut result = String::new();
+    let mut need_zero = false;
+    for (i, group) in groups.iter().enumerate().rev() {
+        if *group == 0 {
+            need_zero = !result.is_empty();
+            continue;
+        }
+
+        // 上の桁があり、この組が千未満の場合は零を入れる (一万零五百)
+        if need_zero || (!result.is_empty() && *group < 1000) {
+            result += DIGITS[0];
+        }
+        need_zero = false;
+
+        let (mut seen, mut zero) = (false, false);
+        for j in (0..4).rev() {
+            let digit = (group / 10u64.pow(j as u32) % 10) as usize;
+
+            if digit == 0 {
+                zero = seen;
+                continue;
+            }
+
+            if zero {
+                result += DIGITS[0];
+                zero = false;
+            }
+
+            // 十〜十九は一を読まない
+            if !(digit == 1 && j == 1 && result.is_empty()) {
+                result += DIGITS[digit];
+            }
+            result += SMALL_UNITS[j];
+            seen = true;
+        }
+
+        result += LARGE_UNITS[i];
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn g2p_known_characters() {
+        let (phones, tones, word2ph) = ChineseG2p::new().g2p("中").unwrap();
+
+        assert_eq!(phones, ["_", "zh", "ong", "_"]);
+        assert_eq!(tones, [0, 1, 1, 0]);
+        assert_eq!(word2ph, [1, 2, 1]);
+    }
+
+    #[test]
+    fn g2p_rejects_out_of_dictionary_character() {
+        // CJK 統合漢字拡張 B は同梱の辞書にない
+        let err = ChineseG2p::new().g2p("中𪚥").unwrap_err();
+        assert!(err.to_string().contains('𪚥'));
+
+        let mut g2p = ChineseG2p::new();
+        g2p.load_pinyin_dict("𪚥 zhe2").unwrap();
+        assert!(g2p.g2p("中𪚥").is_ok());
+    }
+
+    fn tones(text: &str) -> Vec<i32> {
+        ChineseG2p::new()
+            .text_to_pinyin(text)
+            .into_iter()
+            .map(|syllable| syllable.unwrap().1)
+            .collect()
+    }
+
+    #[test]
+    fn bundled_dict_covers_common_text() {
+        let text = "我们今天去图书馆借了三本关于历史的书";
+        assert!(ChineseG2p::new().g2p(text).is_ok());
+    }
+
+    #[test]
+    fn tone_sandhi_yi() {
+        assert_eq!(tones("一天"), [4, 1]);
+        assert_eq!(tones("一定"), [2, 4]);
+        assert_eq!(tones("一"), [1]);
+        // 辞書の単語は辞書の声調を使う
+        assert_eq!(tones("一只"), [4, 1]);
+    }
+
+    #[test]
+    fn tone_sandhi_bu() {
+        assert_eq!(tones("不是"), [2, 4]);
+        assert_eq!(tones("不好"), [4, 3]);
+    }
+
+    #[test]
+    fn tone_sandhi_third_tone_run() {
+        assert_eq!(tones("你好"), [2, 3]);
+        assert_eq!(tones("我很好"), [2, 2, 3]);
+    }
+
+    #[test]
+    fn longest_match_lookup() {
+        let pinyin = |g2p: &ChineseG2p, text: &str| -> Vec<String> {
+            g2p.text_to_pinyin(text)
+                .into_iter()
+                .map(|syllable| syllable.unwrap().0)
+                .collect()
+        };
+
+        let mut g2p = ChineseG2p::new();
+        assert_eq!(pinyin(&g2p, "行"), ["xing"]);
+        assert_eq!(pinyin(&g2p, "银行"), ["yin", "hang"]);
+
+        // 読み込んだ辞書の単語も一文字ずつの読みより優先される
+        assert_eq!(pinyin(&g2p, "行长"), ["xing", "chang"]);
+        g2p.load_pinyin_dict("行长 hang2 zhang3").unwrap();
+        assert_eq!(pinyin(&g2p, "行长"), ["hang", "zhang"]);
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::Sbv2CoreError;
+
+#[derive(Deserialize, Serialize)]
+struct Config {
+    #[serde(default)]
+    data: ConfigData,
+}
+
+#[derive(Deserialize, Serialize, Default)]
+struct ConfigData {
+    #[serde(default)]
+    style2id: HashMap<String, i32>,
+    #[serde(default)]
+    spk2id: HashMap<String, i64>,
+}
+
+/// Style-Bert-VITS2 の config.json のうち、スタイル名と話者名の対応のみ
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ModelConfig {
+    pub style2id: HashMap<String, i32>,
+    pub spk2id: HashMap<String, i64>,
+
+    // 実行時に追加したスタイルの名前 (追加した順)
+    pub custom_styles: Vec<String>,
+}
+
+impl ModelConfig {
+    pub fn from_bytes<T>(config_bytes: T) -> Result<Self, Sbv2CoreError>
+    where
+        T: AsRef<[u8]>,
+    {
+        let config: Config = serde_json::from_slice(config_bytes.as_ref())?;
+
+        Ok(ModelConfig {
+            style2id: config.data.style2id,
+            spk2id: config.data.spk2id,
+            custom_styles: vec![],
+        })
+    }
+
+    /// `from_bytes` で読み込める config.json (スタイル名と話者名のみ) にする
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Sbv2CoreError> {
+        let config = Config {
+            data: ConfigData {
+                style2id: self.style2id.clone(),
+                spk2id: self.spk2id.clone(),
+            },
+        };
+
+        Ok(serde_json::to_vec(&config)?)
+    }
+
+    /// (スタイル名, スタイル ID) を ID 順に返す
+    pub fn styles(&self) -> Vec<(String, i32)> {
+        let mut styles: Vec<(String, i32)> = self
+            .style2id
+            .iter()
+            .map(|(name, id)| (name.clone(), *id))
+            .collect();
+        styles.sort_by_key(|(_, id)| *id);
+
+        styles
+    }
+
+    /// (話者名, 話者 ID) を ID 順に返す
+    pub fn speakers(&self) -> Vec<(String, i64)> {
+        let mut speakers: Vec<(String, i64)> = self
+            .spk2id
+            .iter()
+            .map(|(name, id)| (name.clone(), *id))
+            .collect();
+        speakers.sort_by_key(|(_, id)| *id);
+
+        speakers
+    }
+
+    pub fn style_id(&self, style_name: &str) -> Result<i32, Sbv2CoreError> {
+        self.style2id.get(style_name).copied().ok_or_else(|| {
+            Sbv2CoreError::ValueError(format!(
+                "style `{}` not found (available: {})",
+                style_name,
+                join_names(self.styles())
+            ))
+        })
+    }
+
+    pub fn speaker_id(&self, speaker_name: &str) -> Result<i64, Sbv2CoreError> {
+        self.spk2id.get(speaker_name).copied().ok_or_else(|| {
+            Sbv2CoreError::ValueError(format!(
+                "speaker `{}` not found (available: {})",
+                speaker_name,
+                join_names(self.speakers())
+            ))
+        })
+    }
+}
+
+fn join_names<T>(names: Vec<(String, T)>) -> String {
+    names
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_bytes_round_trip() {
+        let json = r#"{"data": {"style2id": {"Neutral": 0, "Happy": 1}, "spk2id": {"a": 0}}}"#;
+        let mut config = ModelConfig::from_bytes(json).unwrap();
+        config.style2id.insert("Custom".to_string(), 2);
+        config.custom_styles.push("Custom".to_string());
+
+        let loaded = ModelConfig::from_bytes(config.to_bytes().unwrap()).unwrap();
+        assert_eq!(loaded.styles(), config.styles());
+        assert_eq!(loaded.speakers(), config.speakers());
+    }
+}
@@ -0,0 +1,493 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use regex::{Captures, Regex};
+
+use crate::{errors::Sbv2CoreError, norm::PUNCTUATIONS};
+
+// CMUdict 形式 (`WORD  PH0 PH1 ...`) で頻出語 444 語だけを収録した仮の辞書 (CMUdict ではない)
+// 辞書にない単語は綴りから推定するため、実用には CMUdict を `EnglishG2p::load_cmudict` で読み込む
+static STUB_DICT: LazyLock<HashMap<String, Vec<String>>> =
+    LazyLock::new(|| parse_cmudict(include_str!("./english_stub.dict")));
+
+static NUMBER_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[0-9]+(?:,[0-9]{3})*(?:\.[0-9]+)?").unwrap());
+
+const LETTER_PHONES: [&[&str]; 26] = [
+    &["EY1"],
+    &["B", "IY1"],
+    &["S", "IY1"],
+    &["D", "IY1"],
+    &["IY1"],
+    &["EH1", "F"],
+    &["JH", "IY1"],
+    &["EY1", "CH"],
+    &["AY1"],
+    &["JH", "EY1"],
+    &["K", "EY1"],
+    &["EH1", "L"],
+    &["EH1", "M"],
+    &["EH1", "N"],
+    &["OW1"],
+    &["P", "IY1"],
+    &["K", "Y", "UW1"],
+    &["AA1", "R"],
+    &["EH1", "S"],
+    &["T", "IY1"],
+    &["Y", "UW1"],
+    &["V", "IY1"],
+    &["D", "AH1", "B", "AH0", "L", "Y", "UW0"],
+    &["EH1", "K", "S"],
+    &["W", "AY1"],
+    &["Z", "IY1"],
+];
+
+// 辞書にない単語の綴り → ARPAbet
+// 長いものから順に並べる
+const SPELLING_RULES: [(&str, &[&str]); 43] = [
+    ("tion", &["SH", "AH1", "N"]),
+    ("sion", &["ZH", "AH1", "N"]),
+    ("ture", &["CH", "ER1"]),
+    ("ough", &["AO1"]),
+    ("igh", &["AY1"]),
+    ("tch", &["CH"]),
+    ("sch", &["S", "K"]),
+    ("ch", &["CH"]),
+    ("sh", &["SH"]),
+    ("th", &["TH"]),
+    ("ph", &["F"]),
+    ("wh", &["W"]),
+    ("ck", &["K"]),
+    ("ng", &["NG"]),
+    ("qu", &["K", "W"]),
+    ("ee", &["IY1"]),
+    ("ea", &["IY1"]),
+    ("oo", &["UW1"]),
+    ("ou", &["AW1"]),
+    ("ow", &["OW1"]),
+    ("ai", &["EY1"]),
+    ("ay", &["EY1"]),
+    ("ey", &["EY1"]),
+    ("oa", &["OW1"]),
+    ("oi", &["OY1"]),
+    ("oy", &["OY1"]),
+    ("au", &["AO1"]),
+    ("aw", &["AO1"]),
+    ("ew", &["Y", "UW1"]),
+    ("ar", &["AA1", "R"]),
+    ("or", &["AO1", "R"]),
+    ("er", &["ER1"]),
+    ("ir", &["ER1"]),
+    ("ur", &["ER1"]),
+    ("ce", &["S", "EH1"]),
+    ("ci", &["S", "IH1"]),
+    ("cy", &["S", "IY1"]),
+    ("a", &["AE1"]),
+    ("e", &["EH1"]),
+    ("i", &["IH1"]),
+    ("o", &["AA1"]),
+    ("u", &["AH1"]),
+    ("x", &["K", "S"]),
+];
+
+const CONSONANTS: [(char, &str); 19] = [
+    ('b', "B"),
+    ('c', "K"),
+    ('d', "D"),
+    ('f', "F"),
+    ('g', "G"),
+    ('h', "HH"),
+    ('j', "JH"),
+    ('k', "K"),
+    ('l', "L"),
+    ('m', "M"),
+    ('n', "N"),
+    ('p', "P"),
+    ('q', "K"),
+    ('r', "R"),
+    ('s', "S"),
+    ('t', "T"),
+    ('v', "V"),
+    ('w', "W"),
+    ('z', "Z"),
+];
+
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+pub(crate) struct EnglishG2p {
+    user_dict: HashMap<String, Vec<String>>,
+}
+
+impl EnglishG2p {
+    pub fn new() -> Self {
+        EnglishG2p {
+            user_dict: HashMap::new(),
+        }
+    }
+
+    /// CMUdict 形式の辞書を読み込み、同梱の辞書より優先して使う
+    pub fn load_cmudict(&mut self, cmudict: &str) -> Result<(), Sbv2CoreError> {
+        let dict = parse_cmudict(cmudict);
+
+        if dict.is_empty() {
+            return Err(Sbv2CoreError::ValueError(
+                "cmudict has no entries".to_string(),
+            ));
+        }
+
+        self.user_dict.extend(dict);
+        Ok(())
+    }
+
+    /// `words` は BERT のトークンを単語ごとにまとめたもの
+    pub fn g2p(&self, words: &[Vec<String>]) -> (Vec<String>, Vec<i32>, Vec<i32>) {
+        let mut phones = vec!["_".to_string()];
+        let mut tones = vec![0];
+        let mut word2ph = vec![1];
+
+        for word in words {
+            let text: String = word.concat();
+
+            let (word_phones, word_tones) = if PUNCTUATIONS.contains(&text.as_str()) {
+                (vec![text.clone()], vec![0])
+            } else {
+                self.word_to_phones(&text)
+                    .iter()
+                    .map(|ph| refine_ph(ph))
+                    .unzip()
+            };
+
+            word2ph.extend(crate::utils::distribute_phone(
+                word_phones.len() as i32,
+                word.len() as i32,
+            ));
+            phones.extend(word_phones);
+            tones.extend(word_tones);
+        }
+
+        phones.push("_".to_string());
+        tones.push(0);
+        word2ph.push(1);
+
+        (phones, tones, word2ph)
+    }
+
+    fn word_to_phones(&self, word: &str) -> Vec<String> {
+        let letters: String = word.chars().filter(|c| c.is_ascii_alphabetic()).collect();
+        if letters.is_empty() {
+            return vec![];
+        }
+
+        let upper = letters.to_ascii_uppercase();
+        if let Some(phones) = self.user_dict.get(&upper).or_else(|| STUB_DICT.get(&upper)) {
+            return phones.clone();
+        }
+
+        // 全て大文字、または母音を含まない単語はアルファベット読みにする
+        let is_acronym = (letters.len() > 1 && letters.chars().all(|c| c.is_ascii_uppercase()))
+            || !upper.contains(['A', 'E', 'I', 'O', 'U', 'Y']);
+        if is_acronym {
+            return upper
+                .bytes()
+                .flat_map(|c| LETTER_PHONES[(c - b'A') as usize].iter())
+                .map(|ph| ph.to_string())
+                .collect();
+        }
+
+        spelling_to_phones(&letters.to_ascii_lowercase())
+    }
+}
+
+fn parse_cmudict(cmudict: &str) -> HashMap<String, Vec<String>> {
+    let mut map = HashMap::new();
+
+    for line in cmudict.lines() {
+        if line.starts_with(";;;") {
+            continue;
+        }
+
+        let Some((word, phones)) = line.split_once(' ') else {
+            continue;
+        };
+
+        // 同じ単語の別の読み (`WORD(1)`) は使わない
+        if word.ends_with(')') {
+            continue;
+        }
+
+        let phones: Vec<String> = phones.split_whitespace().map(|x| x.to_string()).collect();
+        if !phones.is_empty() {
+            map.insert(word.to_ascii_uppercase(), phones);
+        }
+    }
+
+    map
+}
+
+// ARPAbet の強勢 (0, 1, 2) を音調 (1, 2, 3) に、強勢のない子音を 3 にする
+fn refine_ph(phone: &str) -> (String, i32) {
+    let (phone, tone) = match phone.chars().last().and_then(|c| c.to_digit(10)) {
+        Some(stress) => (&phone[..phone.len() - 1], stress as i32 + 1),
+        None => (phone, 3),
+    };
+
+    let phone = match phone.to_lowercase().as_str() {
+        "v" => "V".to_string(),
+        lower => lower.to_string(),
+    };
+
+    (phone, tone)
+}
+
+/// 辞書にない単語を綴りから推定して ARPAbet にする
+fn spelling_to_phones(word: &str) -> Vec<String> {
+    let chars: Vec<char> = word.chars().collect();
+    let mut phones: Vec<&str> = vec![];
+
+    // 語末の e は読まず、その前の母音を長母音にする (make, time, note, cute)
+    let magic_e = chars.len() >= 4
+        && chars[chars.len() - 1] == 'e'
+        && !"aeiou".contains(chars[chars.len() - 2])
+        && "aiou".contains(chars[chars.len() - 3])
+        && !"aeiou".contains(chars[chars.len() - 4]);
+    let end = if chars.len() > 2
+        && chars[chars.len() - 1] == 'e'
+        && !"aeiou".contains(chars[chars.len() - 2])
+    {
+        word.len() - 1
+    } else {
+        word.len()
+    };
+
+    let mut i = 0;
+    while i < end {
+        let rest = &word[i..end];
+
+        if magic_e && i == end - 2 {
+            phones.push(match chars[i] {
+                'a' => "EY1",
+                'i' => "AY1",
+                'o' => "OW1",
+                _ => "UW1",
+            });
+            i += 1;
+            continue;
+        }
+
+        if let Some((from, to)) = SPELLING_RULES
+            .iter()
+            .find(|(from, _)| rest.starts_with(from))
+        {
+            phones.extend(to.iter());
+            i += from.len();
+            continue;
+        }
+
+        let c = chars[i];
+        match c {
+            'y' if i == 0 => phones.push("Y"),
+            'y' if i == end - 1 => phones.push("IY0"),
+            'y' => phones.push("IH1"),
+            _ => {
+                // 同じ子音が続く場合は一つにまとめる
+                if i > 0 && chars[i - 1] == c {
+                    i += 1;
+                    continue;
+                }
+
+                if let Some((_, ph)) = CONSONANTS.iter().find(|(x, _)| *x == c) {
+                    phones.push(*ph);
+                }
+            }
+        }
+        i += 1;
+    }
+
+    // 最初の母音のみ第一強勢にする
+    let mut stressed = false;
+    phones
+        .iter()
+        .map(|ph| match ph.strip_suffix('1') {
+            Some(vowel) if stressed => format!("{vowel}0"),
+            Some(_) => {
+                stressed = true;
+                ph.to_string()
+            }
+            None => ph.to_string(),
+        })
+        .collect()
+}
+
+/// 英語のテキストを G2P の前に正規化する
+pub fn normalize_text(text: &str) -> String {
+    let text = NUMBER_PATTERN
+        .replace_all(text, |caps: &Captures| {
+            format!(" {} ", number_to_words(&caps[0]))
+        })
+        .to_string();
+
+    crate::norm::replace_punctuation_en(text)
+}
+
+fn number_to_words(number: &str) -> String {
+    let number = number.replace(',', "");
+    let (integer, decimal) = match number.split_once('.') {
+        Some((integer, decimal)) => (integer, Some(decimal)),
+        None => (number.as_str(), None),
+    };
+
+    let mut words = match integer.parse::<u64>() {
+        Ok(n) => integer_to_words(n),
+        Err(_) => digits_to_words(integer),
+    };
+
+    if let Some(decimal) = decimal {
+        words += " point ";
+        words += &digits_to_words(decimal);
+    }
+
+    words
+}
+
+fn digits_to_words(digits: &str) -> String {
+    digits
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|d| ONES[d as usize])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn integer_to_words(n: u64) -> String {
+    const SCALES: [(u64, &str); 4] = [
+        (1_000_000_000_000, "trillion"),
+        (1_000_000_000, "billion"),
+        (1_000_000, "million"),
+        (1_000, "thousand"),
+    ];
+
+    if n < 20 {
+        return ONES[n as usize].to_string();
+    }
+
+    if n < 100 {
+        return match n % 10 {
+            0 => TENS[(n / 10) as usize].to_string(),
+            ones => format!("{} {}", TENS[(n / 10) as usize], ONES[ones as usize]),
+        };
+    }
+
+    if n < 1000 {
+        return match n % 100 {
+            0 => format!("{} hundred", ONES[(n / 100) as usize]),
+            rest => format!(
+                "{} hundred {}",
+                ONES[(n / 100) as usize],
+                integer_to_words(rest)
+            ),
+        };
+    }
+
+    let (scale, name) = SCALES.iter().find(|(scale, _)| n >= *scale).unwrap();
+    match n % scale {
+        0 => format!("{} {}", integer_to_words(n / scale), name),
+        rest => format!(
+            "{} {} {}",
+            integer_to_words(n / scale),
+            name,
+            integer_to_words(rest)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refine_ph_maps_stress_to_tone() {
+        assert_eq!(refine_ph("AH0"), ("ah".to_string(), 1));
+        assert_eq!(refine_ph("EY1"), ("ey".to_string(), 2));
+        assert_eq!(refine_ph("AO2"), ("ao".to_string(), 3));
+        assert_eq!(refine_ph("K"), ("k".to_string(), 3));
+        assert_eq!(refine_ph("V"), ("V".to_string(), 3));
+    }
+
+    #[test]
+    fn spelling_to_phones_magic_e() {
+        assert_eq!(spelling_to_phones("make"), ["M", "EY1", "K"]);
+        assert_eq!(spelling_to_phones("time"), ["T", "AY1", "M"]);
+        assert_eq!(spelling_to_phones("note"), ["N", "OW1", "T"]);
+        assert_eq!(spelling_to_phones("cute"), ["K", "UW1", "T"]);
+    }
+
+    #[test]
+    fn spelling_to_phones_digraphs() {
+        assert_eq!(spelling_to_phones("ship"), ["SH", "IH1", "P"]);
+        assert_eq!(spelling_to_phones("night"), ["N", "AY1", "T"]);
+        assert_eq!(spelling_to_phones("phone"), ["F", "OW1", "N"]);
+        // 最初の母音のみ第一強勢
+        assert_eq!(
+            spelling_to_phones("station"),
+            ["S", "T", "AE1", "SH", "AH0", "N"]
+        );
+    }
+
+    #[test]
+    fn number_to_words_reads_numbers() {
+        assert_eq!(number_to_words("0"), "zero");
+        assert_eq!(number_to_words("42"), "forty two");
+        assert_eq!(number_to_words("1,980"), "one thousand nine hundred eighty");
+        assert_eq!(number_to_words("3.14"), "three point one four");
+        assert_eq!(number_to_words("2000000"), "two million");
+    }
+
+    #[test]
+    fn g2p_distributes_phones_over_tokens() {
+        let words = vec![
+            vec!["hel".to_string(), "lo".to_string()],
+            vec!["API".to_string()],
+            vec![".".to_string()],
+        ];
+        let (phones, tones, word2ph) = EnglishG2p::new().g2p(&words);
+
+        assert_eq!(phones.len(), tones.len());
+        assert_eq!(word2ph.iter().sum::<i32>() as usize, phones.len());
+        // 前後の `_` と、単語のトークンごとの音素数
+        assert_eq!(word2ph.len(), 1 + 2 + 1 + 1 + 1);
+        assert_eq!(&phones[phones.len() - 2..], [".", "_"]);
+    }
+
+    #[test]
+    fn user_dict_overrides_bundled_dict() {
+        let mut g2p = EnglishG2p::new();
+        assert!(g2p.load_cmudict(";;; comment").is_err());
+
+        g2p.load_cmudict("HELLO  HH EH0 L OW1\nHELLO(1)  HH AH0 L OW1")
+            .unwrap();
+        assert_eq!(g2p.word_to_phones("Hello"), ["HH", "EH0", "L", "OW1"]);
+    }
+}
@@ -0,0 +1,481 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use regex::{Captures, Regex};
+
+use crate::errors::Sbv2CoreError;
+
+static ENGLISH_KANA_DICT: LazyLock<HashMap<String, String>> =
+    LazyLock::new(|| serde_json::from_str(include_str!("./english_kana_dict.json")).unwrap());
+
+static WORD_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[A-Za-zＡ-Ｚａ-ｚ]+(?:'[A-Za-z]+)*").unwrap());
+
+const LETTER_READINGS: [&str; 26] = [
+    "エー",
+    "ビー",
+    "シー",
+    "ディー",
+    "イー",
+    "エフ",
+    "ジー",
+    "エイチ",
+    "アイ",
+    "ジェー",
+    "ケー",
+    "エル",
+    "エム",
+    "エヌ",
+    "オー",
+    "ピー",
+    "キュー",
+    "アール",
+    "エス",
+    "ティー",
+    "ユー",
+    "ブイ",
+    "ダブリュー",
+    "エックス",
+    "ワイ",
+    "ゼット",
+];
+
+// 綴り → ローマ字風の発音表記 (`:` は長音)
+// 長いものから順に並べる
+const SPELLING_RULES: [(&str, &str); 40] = [
+    ("ough", "o:"),
+    ("tion", "shon"),
+    ("sion", "jon"),
+    ("ture", "cha:"),
+    ("igh", "ai"),
+    ("tch", "cch"),
+    ("sch", "sk"),
+    ("sce", "se"),
+    ("sci", "si"),
+    ("ch", "ch"),
+    ("sh", "sh"),
+    ("th", "s"),
+    ("ph", "f"),
+    ("wh", "w"),
+    ("ck", "kk"),
+    ("qu", "kw"),
+    ("ee", "i:"),
+    ("ea", "i:"),
+    ("oo", "u:"),
+    ("ou", "au"),
+    ("ow", "ou"),
+    ("ai", "ei"),
+    ("ay", "ei"),
+    ("ey", "ei"),
+    ("oa", "o:"),
+    ("oi", "oi"),
+    ("oy", "oi"),
+    ("au", "o:"),
+    ("aw", "o:"),
+    ("ew", "yu:"),
+    ("ll", "r"),
+    ("ss", "s"),
+    ("ce", "se"),
+    ("ci", "si"),
+    ("cy", "si"),
+    ("c", "k"),
+    ("x", "ks"),
+    ("l", "r"),
+    ("v", "b"),
+    ("q", "k"),
+];
+
+// 後ろに母音が続かない場合のみ適用する
+const R_RULES: [(&str, &str); 5] = [
+    ("ar", "a:"),
+    ("or", "o:"),
+    ("er", "a:"),
+    ("ir", "a:"),
+    ("ur", "a:"),
+];
+
+const VOWELS: [char; 5] = ['a', 'i', 'u', 'e', 'o'];
+
+pub(crate) struct EnglishKana {
+    user_dict: HashMap<String, String>,
+}
+
+impl EnglishKana {
+    pub fn new() -> Self {
+        EnglishKana {
+            user_dict: HashMap::new(),
+        }
+    }
+
+    pub fn add_word(&mut self, word: &str, kana: &str) -> Result<(), Sbv2CoreError> {
+        if word.is_empty() || !word.chars().all(|c| c.is_ascii_alphabetic() || c == '\'') {
+            return Err(Sbv2CoreError::ValueError(format!(
+                "Word must be latin alphabet only: {}",
+                word
+            )));
+        }
+
+        if kana.is_empty() || !kana.chars().all(|c| ('\u{30A0}'..='\u{30FF}').contains(&c)) {
+            return Err(Sbv2CoreError::ValueError(format!(
+                "Reading must be katakana only: {}",
+                kana
+            )));
+        }
+
+        self.user_dict
+            .insert(word.to_ascii_lowercase(), kana.to_string());
+
+        Ok(())
+    }
+
+    pub fn remove_word(&mut self, word: &str) -> bool {
+        self.user_dict.remove(&word.to_ascii_lowercase()).is_some()
+    }
+
+    /// テキスト中の英単語をカタカナに変換する
+    pub fn convert(&self, text: &str) -> String {
+        WORD_PATTERN
+            .replace_all(text, |caps: &Captures| {
+                // 全角英字を半角にする
+                let word: String = caps[0]
+                    .chars()
+                    .map(|c| match c {
+                        'Ａ'..='Ｚ' | 'ａ'..='ｚ' => char::from_u32(c as u32 - 0xFEE0).unwrap(),
+                        _ => c,
+                    })
+                    .collect();
+
+                self.word_to_kana(&word)
+            })
+            .to_string()
+    }
+
+    fn lookup(&self, word: &str) -> Option<String> {
+        let lower = word.to_ascii_lowercase();
+
+        self.user_dict
+            .get(&lower)
+            .or_else(|| ENGLISH_KANA_DICT.get(&lower))
+            .cloned()
+    }
+
+    fn word_to_kana(&self, word: &str) -> String {
+        if let Some(kana) = self.lookup(word) {
+            return kana;
+        }
+
+        if is_acronym(word) {
+            return spell_letters(word);
+        }
+
+        // GitHub, FastAPI など大文字の位置で分割できる場合は部分ごとに変換する
+        let parts = split_camel_case(word);
+        if parts.len() > 1 {
+            return parts.iter().map(|part| self.word_to_kana(part)).collect();
+        }
+
+        spelling_to_kana(word)
+    }
+}
+
+fn is_acronym(word: &str) -> bool {
+    let letters: Vec<char> = word.chars().filter(|c| c.is_ascii_alphabetic()).collect();
+
+    // 全て大文字、または母音を含まない単語 (npm, ssh など) はアルファベット読みにする
+    letters.iter().all(|c| c.is_ascii_uppercase())
+        || !letters
+            .iter()
+            .any(|c| VOWELS.contains(&c.to_ascii_lowercase()) || *c == 'y' || *c == 'Y')
+}
+
+fn spell_letters(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .map(|c| LETTER_READINGS[(c.to_ascii_uppercase() as u8 - b'A') as usize])
+        .collect()
+}
+
+fn split_camel_case(word: &str) -> Vec<String> {
+    let chars: Vec<char> = word.chars().collect();
+    let mut parts = vec![];
+    let mut current = String::new();
+
+    for (i, c) in chars.iter().enumerate() {
+        let boundary = i > 0
+            && c.is_ascii_uppercase()
+            && (chars[i - 1].is_ascii_lowercase()
+                || chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase()));
+
+        if boundary && !current.is_empty() {
+            parts.push(std::mem::take(&mut current));
+        }
+        current.push(*c);
+    }
+
+    if !current.is_empty() {
+        parts.push(current);
+    }
+
+    parts
+}
+
+/// 辞書にない単語を綴りから推定してカタカナにする
+fn spelling_to_kana(word: &str) -> String {
+    let mut word: String = word
+        .chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+
+    // make → meik, time → taim のように語末の e の前の母音を長くする
+    let chars: Vec<char> = word.chars().collect();
+    if chars.len() >= 4 && chars[chars.len() - 1] == 'e' {
+        let (vowel, consonant) = (chars[chars.len() - 3], chars[chars.len() - 2]);
+
+        if !VOWELS.contains(&consonant) && !VOWELS.contains(&chars[chars.len() - 4]) {
+            let long = match vowel {
+                'a' => Some("ei"),
+                'i' => Some("ai"),
+                'o' => Some("ou"),
+                'u' => Some("yu:"),
+                _ => None,
+            };
+
+            if let Some(long) = long {
+                // cute → kyu:t (cy → si にしない)
+                let head = &word[..word.len() - 3];
+                let head = match head.strip_suffix('c') {
+                    Some(stripped) if vowel == 'u' => format!("{stripped}k"),
+                    _ => head.to_string(),
+                };
+
+                word = format!("{}{}{}", head, long, consonant);
+            }
+        }
+    }
+
+    spelling_to_romaji(&word)
+        .map(|romaji| romaji_to_kana(&romaji))
+        .unwrap_or_default()
+}
+
+fn spelling_to_romaji(word: &str) -> Option<String> {
+    let mut romaji = String::new();
+    let mut rest = word;
+
+    while !rest.is_empty() {
+        let next_is_vowel = |len: usize| {
+            rest[len..]
+                .chars()
+                .next()
+                .is_some_and(|c| VOWELS.contains(&c))
+        };
+
+        if let Some((from, to)) = R_RULES
+            .iter()
+            .find(|(from, _)| rest.starts_with(from) && !next_is_vowel(from.len()))
+        {
+            romaji += to;
+            rest = &rest[from.len()..];
+            continue;
+        }
+
+        // 語末の y は長音のイ (happy → happi:)
+        if rest == "y" && !romaji.is_empty() {
+            romaji += "i:";
+            break;
+        }
+
+        if let Some((from, to)) = SPELLING_RULES
+            .iter()
+            .find(|(from, _)| rest.starts_with(from))
+        {
+            romaji += to;
+            rest = &rest[from.len()..];
+            continue;
+        }
+
+        let c = rest.chars().next()?;
+        match c {
+            // 子音の後の y は母音のイとして扱う
+            'y' if !romaji.is_empty() && !next_is_vowel(1) => romaji.push('i'),
+            _ => romaji.push(c),
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+
+    Some(romaji)
+}
+
+fn romaji_to_kana(romaji: &str) -> String {
+    let chars: Vec<char> = romaji.chars().collect();
+    let mut result = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == ':' {
+            result += "ー";
+            i += 1;
+            continue;
+        }
+
+        if VOWELS.contains(&c) {
+            result += consonant_vowel_to_kana("", c);
+            i += 1;
+            continue;
+        }
+
+        let consonant = match (c, chars.get(i + 1)) {
+            ('c', Some('h')) => "ch",
+            ('s', Some('h')) => "sh",
+            ('t', Some('s')) => "ts",
+            ('k', Some('w')) => "kw",
+            _ => &romaji[i..i + 1],
+        };
+        i += consonant.len();
+
+        let next = chars.get(i).copied();
+
+        match next {
+            Some(v) if VOWELS.contains(&v) => {
+                result += consonant_vowel_to_kana(consonant, v);
+                i += 1;
+            }
+
+            // キャ, ビュー などの拗音
+            Some('y')
+                if consonant.len() == 1
+                    && "kgnhbpmr".contains(consonant)
+                    && chars.get(i + 1).is_some_and(|v| "auo".contains(*v)) =>
+            {
+                result += consonant_vowel_to_kana(consonant, 'i');
+                result += match chars[i + 1] {
+                    'a' => "ャ",
+                    'u' => "ュ",
+                    _ => "ョ",
+                };
+                i += 2;
+            }
+
+            // 同じ子音が続く場合、k, p, t, s, ch の前は促音にし、それ以外は一つにまとめる
+            Some(n) if consonant.len() == 1 && n == c => {
+                if "kptsc".contains(c) {
+                    result += "ッ";
+                }
+            }
+
+            _ => result += lone_consonant_to_kana(consonant, next),
+        }
+    }
+
+    result
+}
+
+fn consonant_vowel_to_kana(consonant: &str, vowel: char) -> &'static str {
+    let row: [&str; 5] = match consonant {
+        "" => ["ア", "イ", "ウ", "エ", "オ"],
+        "k" => ["カ", "キ", "ク", "ケ", "コ"],
+        "g" => ["ガ", "ギ", "グ", "ゲ", "ゴ"],
+        "s" => ["サ", "シ", "ス", "セ", "ソ"],
+        "z" => ["ザ", "ジ", "ズ", "ゼ", "ゾ"],
+        "t" => ["タ", "ティ", "トゥ", "テ", "ト"],
+        "d" => ["ダ", "ディ", "ドゥ", "デ", "ド"],
+        "n" => ["ナ", "ニ", "ヌ", "ネ", "ノ"],
+        "h" => ["ハ", "ヒ", "フ", "ヘ", "ホ"],
+        "b" => ["バ", "ビ", "ブ", "ベ", "ボ"],
+        "p" => ["パ", "ピ", "プ", "ペ", "ポ"],
+        "m" => ["マ", "ミ", "ム", "メ", "モ"],
+        "y" => ["ヤ", "イ", "ユ", "イェ", "ヨ"],
+        "r" => ["ラ", "リ", "ル", "レ", "ロ"],
+        "w" => ["ワ", "ウィ", "ウ", "ウェ", "ウォ"],
+        "f" => ["ファ", "フィ", "フ", "フェ", "フォ"],
+        "j" => ["ジャ", "ジ", "ジュ", "ジェ", "ジョ"],
+        "ch" => ["チャ", "チ", "チュ", "チェ", "チョ"],
+        "sh" => ["シャ", "シ", "シュ", "シェ", "ショ"],
+        "ts" => ["ツァ", "ツィ", "ツ", "ツェ", "ツォ"],
+        "kw" => ["クワ", "クイ", "ク", "クエ", "クオ"],
+        _ => ["ア", "イ", "ウ", "エ", "オ"],
+    };
+
+    let index = VOWELS.iter().position(|v| *v == vowel).unwrap_or(0);
+    row[index]
+}
+
+fn lone_consonant_to_kana(consonant: &str, next: Option<char>) -> &'static str {
+    match consonant {
+        // b, p, m の前の m は撥音
+        "m" if next.is_some_and(|n| "bpm".contains(n)) => "ン",
+        "n" => "ン",
+        "k" | "q" => "ク",
+        "g" => "グ",
+        "s" => "ス",
+        "z" => "ズ",
+        "t" => "ト",
+        "d" => "ド",
+        "h" => "",
+        "b" => "ブ",
+        "p" => "プ",
+        "m" => "ム",
+        "y" => "イ",
+        "r" => "ル",
+        "w" => "ウ",
+        "f" => "フ",
+        "j" => "ジ",
+        "ch" => "チ",
+        "sh" => "シュ",
+        "ts" => "ツ",
+        "kw" => "ク",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_dictionary_words() {
+        let kana = EnglishKana::new();
+        assert_eq!(kana.convert("Hello world"), "ハロー ワールド");
+        assert_eq!(kana.convert("ｐｙｔｈｏｎ"), "パイソン");
+    }
+
+    #[test]
+    fn convert_acronyms() {
+        let kana = EnglishKana::new();
+        assert_eq!(kana.convert("API"), "エーピーアイ");
+        assert_eq!(kana.convert("npm"), "エヌピーエム");
+    }
+
+    #[test]
+    fn convert_camel_case() {
+        assert_eq!(split_camel_case("FastAPI"), ["Fast", "API"]);
+        assert_eq!(split_camel_case("GitHubCLI"), ["Git", "Hub", "CLI"]);
+        assert_eq!(
+            EnglishKana::new().convert("FastAPI"),
+            "ファストエーピーアイ"
+        );
+    }
+
+    #[test]
+    fn convert_unknown_words_by_spelling() {
+        let kana = EnglishKana::new();
+        assert_eq!(kana.convert("make"), "メイク");
+        assert_eq!(kana.convert("cute"), "キュート");
+        assert_eq!(kana.convert("desk"), "デスク");
+    }
+
+    #[test]
+    fn add_word_overrides_dictionary() {
+        let mut kana = EnglishKana::new();
+        kana.add_word("Hello", "ヘロー").unwrap();
+        assert_eq!(kana.convert("hello"), "ヘロー");
+
+        assert!(kana.remove_word("HELLO"));
+        assert_eq!(kana.convert("hello"), "ハロー");
+
+        assert!(kana.add_word("hello!", "ハロー").is_err());
+        assert!(kana.add_word("hello", "はろー").is_err());
+    }
+}
@@ -23,6 +23,12 @@ pub enum Sbv2CoreError {
     #[error("JPreprocess error: {0}")]
     JPreprocessError(#[from] jpreprocess::error::JPreprocessError),
 
+    #[error("model signature error: {}", .0.join(", "))]
+    ModelSignatureError(Vec<String>),
+
+    #[error("execution provider error: {0}")]
+    ExecutionProviderError(String),
+
     #[error("Value error: {0}")]
     ValueError(String),
 
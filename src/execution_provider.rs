@@ -0,0 +1,533 @@
+use std::collections::HashMap;
+
+use ort::{
+    CPUExecutionProvider, CUDAExecutionProvider, CUDAExecutionProviderCuDNNConvAlgoSearch,
+    CoreMLExecutionProvider, DirectMLExecutionProvider, ExecutionProvider, SessionBuilder,
+    TensorRTExecutionProvider,
+};
+
+use crate::errors::Sbv2CoreError;
+
+/// Execution provider of an ONNX session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExecutionProviderKind {
+    Cpu,
+    Cuda,
+    TensorRT,
+    DirectML,
+    CoreML,
+}
+
+/// What to do when an execution provider cannot be registered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderFallback {
+    /// Skip the provider (CPU is used if none of them can be registered)
+    Next,
+    /// Return an error
+    Error,
+}
+
+/// Execution provider to register for an ONNX session
+///
+/// # Fields
+/// - `kind`: Execution provider
+/// - `device_id`: Device ID (CUDA, TensorRT and DirectML)
+/// - `options`: Provider options
+///     - CUDA: `tf32` (bool), `cudnn_conv_algo_search` (`exhaustive`, `heuristic` or `default`), `gpu_mem_limit` (bytes)
+///     - TensorRT: `fp16` (bool), `max_workspace_size` (bytes),
+///       `profile_min_shapes`, `profile_opt_shapes`, `profile_max_shapes` (e.g. `input_ids:1x1,attention_mask:1x1`),
+///       `profile_min_length`, `profile_opt_length`, `profile_max_length`, `profile_max_batch`
+///       (used with the model inputs to derive the profile shapes when they are not given; tokens for BERT, phones for VITS2),
+///       `engine_cache_path` (directory), `timing_cache` (bool)
+///     - CoreML: `cpu_only`, `ane_only`, `subgraphs` (bool)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionProviderConfig {
+    pub kind: ExecutionProviderKind,
+    pub device_id: i32,
+    pub options: HashMap<String, String>,
+}
+
+impl ExecutionProviderConfig {
+    pub fn new(kind: ExecutionProviderKind) -> Self {
+        ExecutionProviderConfig {
+            kind,
+            device_id: 0,
+            options: HashMap::new(),
+        }
+    }
+
+    pub fn with_device_id(mut self, device_id: i32) -> Self {
+        self.device_id = device_id;
+        self
+    }
+
+    pub fn with_option(mut self, key: &str, value: &str) -> Self {
+        self.options.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+/// 有効な feature に応じた provider の一覧 (以前のコンパイル時の選択と同じ順)
+pub(crate) fn default_execution_providers() -> Vec<ExecutionProviderConfig> {
+    let mut providers = vec![];
+
+    if cfg!(feature = "tensorrt") {
+        providers.push(
+            ExecutionProviderConfig::new(ExecutionProviderKind::TensorRT)
+                .with_option("fp16", "true"),
+        );
+    }
+
+    if cfg!(feature = "cuda") {
+        let mut cuda = ExecutionProviderConfig::new(ExecutionProviderKind::Cuda)
+            .with_option("cudnn_conv_algo_search", "default");
+
+        if cfg!(feature = "cuda_tf32") {
+            cuda = cuda.with_option("tf32", "true");
+        }
+
+        providers.push(cuda);
+    }
+
+    if cfg!(feature = "directml") {
+        providers.push(ExecutionProviderConfig::new(
+            ExecutionProviderKind::DirectML,
+        ));
+    }
+
+    if cfg!(feature = "coreml") {
+        providers.push(ExecutionProviderConfig::new(ExecutionProviderKind::CoreML));
+    }
+
+    providers
+}
+
+/// `providers` を順にすべて登録し、登録できた provider を優先順に返す (最初が主に使われる provider)
+///
+/// 登録した順に優先され、前の provider で実行できないノードは次の provider で実行される
+/// (TensorRT で実行できないノードを CUDA で実行するなど)。
+/// CPU は常に最後に登録する (`providers` に含める場合は最後にする)
+pub(crate) fn register_execution_providers(
+    builder: &SessionBuilder,
+    providers: &[ExecutionProviderConfig],
+    fallback: ProviderFallback,
+    memory_arena: bool,
+    is_bert: bool,
+    model_bytes: &[u8],
+) -> Result<Vec<ExecutionProviderKind>, Sbv2CoreError> {
+    check_cpu_is_last(providers)?;
+
+    let cpu = CPUExecutionProvider::default().with_arena_allocator(memory_arena);
+    let mut registered = vec![];
+
+    for config in providers {
+        let provider: Box<dyn ExecutionProvider> = match config.kind {
+            ExecutionProviderKind::Cpu => continue,
+            ExecutionProviderKind::Cuda => Box::new(build_cuda(config)?),
+            ExecutionProviderKind::TensorRT => {
+                Box::new(build_tensorrt(config, is_bert, model_bytes)?)
+            }
+            ExecutionProviderKind::DirectML => {
+                check_options(config, &[])?;
+                Box::new(DirectMLExecutionProvider::default().with_device_id(config.device_id))
+            }
+            ExecutionProviderKind::CoreML => Box::new(build_coreml(config)?),
+        };
+
+        let result = provider.is_available().and_then(|available| {
+            if available {
+                provider.register(builder).map(|_| true)
+            } else {
+                Ok(false)
+            }
+        });
+
+        let reason = match result {
+            Ok(true) => {
+                registered.push(config.kind);
+                continue;
+            }
+            Ok(false) => "not available".to_string(),
+            Err(e) => e.to_string(),
+        };
+
+        if fallback == ProviderFallback::Error {
+            return Err(Sbv2CoreError::ExecutionProviderError(format!(
+                "{:?} (device {}): {}",
+                config.kind, config.device_id, reason
+            )));
+        }
+    }
+
+    cpu.register(builder)?;
+    registered.push(ExecutionProviderKind::Cpu);
+
+    Ok(registered)
+}
+
+// CPU はすべてのノードを実行できるので、後ろの provider は使われない
+fn check_cpu_is_last(providers: &[ExecutionProviderConfig]) -> Result<(), Sbv2CoreError> {
+    let cpu_position = providers
+        .iter()
+        .position(|config| config.kind == ExecutionProviderKind::Cpu);
+
+    match cpu_position {
+        Some(i) if i + 1 != providers.len() => Err(Sbv2CoreError::ExecutionProviderError(format!(
+            "Cpu must be the last execution provider, but {:?} is listed after it",
+            providers[i + 1].kind
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn build_cuda(config: &ExecutionProviderConfig) -> Result<CUDAExecutionProvider, Sbv2CoreError> {
+    check_options(config, &["tf32", "cudnn_conv_algo_search", "gpu_mem_limit"])?;
+
+    let mut cuda = CUDAExecutionProvider::default().with_device_id(config.device_id);
+
+    if let Some(tf32) = parse_option::<bool>(config, "tf32")? {
+        cuda = cuda.with_tf32(tf32);
+    }
+    if let Some(search) = config.options.get("cudnn_conv_algo_search") {
+        let search = match search.as_str() {
+            "exhaustive" => CUDAExecutionProviderCuDNNConvAlgoSearch::Exhaustive,
+            "heuristic" => CUDAExecutionProviderCuDNNConvAlgoSearch::Heuristic,
+            "default" => CUDAExecutionProviderCuDNNConvAlgoSearch::Default,
+            _ => return Err(invalid_option(config, "cudnn_conv_algo_search")),
+        };
+        cuda = cuda.with_conv_algorithm_search(search);
+    }
+    if let Some(limit) = parse_option::<usize>(config, "gpu_mem_limit")? {
+        cuda = cuda.with_memory_limit(limit);
+    }
+
+    Ok(cuda)
+}
+
+// TensorRT のプロファイルの既定の (最小, 最適, 最大) の系列長
+const BERT_PROFILE_LENGTHS: (usize, usize, usize) = (1, 64, 512);
+const VITS2_PROFILE_LENGTHS: (usize, usize, usize) = (1, 128, 1024);
+
+// バッチの次元がない VITS2 の入力
+const SCALAR_INPUTS: [&str; 4] = ["sdp_ratio", "length_scale", "noise_scale", "noise_scale_w"];
+
+fn build_tensorrt(
+    config: &ExecutionProviderConfig,
+    is_bert: bool,
+    model_bytes: &[u8],
+) -> Result<TensorRTExecutionProvider, Sbv2CoreError> {
+    check_options(
+        config,
+        &[
+            "fp16",
+            "max_workspace_size",
+            "profile_min_shapes",
+            "profile_opt_shapes",
+            "profile_max_shapes",
+            "profile_min_length",
+            "profile_opt_length",
+            "profile_max_length",
+            "profile_max_batch",
+            "engine_cache_path",
+            "timing_cache",
+        ],
+    )?;
+
+    // 形状が指定されていない場合のみ、モデルの入力からプロファイルを求める
+    let model_inputs = match config.options.contains_key("profile_min_shapes") {
+        true => vec![],
+        false => crate::model::input_shapes(model_bytes)?,
+    };
+    let (min_shapes, opt_shapes, max_shapes) = tensorrt_profile(config, is_bert, &model_inputs)?;
+    let mut tensorrt = TensorRTExecutionProvider::default()
+        .with_device_id(config.device_id)
+        .with_profile_min_shapes(min_shapes)
+        .with_profile_opt_shapes(opt_shapes)
+        .with_profile_max_shapes(max_shapes);
+
+    if let Some(fp16) = parse_option::<bool>(config, "fp16")? {
+        tensorrt = tensorrt.with_fp16(fp16);
+    }
+    if let Some(size) = parse_option::<usize>(config, "max_workspace_size")? {
+        tensorrt = tensorrt.with_max_workspace_size(size);
+    }
+    if let Some(path) = config.options.get("engine_cache_path") {
+        tensorrt = tensorrt
+            .with_engine_cache(true)
+            .with_engine_cache_path(path.as_str());
+    }
+    if let Some(timing_cache) = parse_option::<bool>(config, "timing_cache")? {
+        tensorrt = tensorrt.with_timing_cache(timing_cache);
+    }
+
+    Ok(tensorrt)
+}
+
+// (min, opt, max) のプロファイル。指定されていなければモデルの入力の形状と系列長、バッチサイズから求める
+fn tensorrt_profile(
+    config: &ExecutionProviderConfig,
+    is_bert: bool,
+    model_inputs: &[(String, Vec<i64>)],
+) -> Result<(String, String, String), Sbv2CoreError> {
+    let keys = [
+        "profile_min_shapes",
+        "profile_opt_shapes",
+        "profile_max_shapes",
+    ];
+    match keys.map(|key| config.options.get(key)) {
+        [Some(min), Some(opt), Some(max)] => {
+            return Ok((min.clone(), opt.clone(), max.clone()));
+        }
+        [None, None, None] => {}
+        _ => {
+            return Err(Sbv2CoreError::ExecutionProviderError(format!(
+                "{} must be given together",
+                keys.join(", ")
+            )))
+        }
+    }
+
+    let (min, opt, max) = match is_bert {
+        true => BERT_PROFILE_LENGTHS,
+        false => VITS2_PROFILE_LENGTHS,
+    };
+    let min = parse_option(config, "profile_min_length")?.unwrap_or(min);
+    let opt = parse_option(config, "profile_opt_length")?.unwrap_or(opt);
+    let max = parse_option(config, "profile_max_length")?.unwrap_or(max);
+    let max_batch = parse_option(config, "profile_max_batch")?.unwrap_or(1);
+
+    if !(1 <= min && min <= opt && opt <= max) || max_batch == 0 {
+        return Err(Sbv2CoreError::ExecutionProviderError(format!(
+            "invalid TensorRT profile: length {}/{}/{}, batch {}",
+            min, opt, max, max_batch
+        )));
+    }
+
+    Ok((
+        profile_shapes(model_inputs, 1, min),
+        profile_shapes(model_inputs, 1, opt),
+        profile_shapes(model_inputs, max_batch, max),
+    ))
+}
+
+// 動的な次元を持つ入力の形状
+// (最初の次元はバッチ、それ以外は系列長とし、固定の次元はモデルのまま)
+fn profile_shapes(model_inputs: &[(String, Vec<i64>)], batch: usize, length: usize) -> String {
+    model_inputs
+        .iter()
+        .filter(|(_, dimensions)| dimensions.iter().any(|dim| *dim < 0))
+        .map(|(name, dimensions)| {
+            let shape: Vec<String> = dimensions
+                .iter()
+                .enumerate()
+                .map(|(i, dim)| match (*dim < 0, i) {
+                    (false, _) => dim.to_string(),
+                    (true, 0) if SCALAR_INPUTS.contains(&name.as_str()) => "1".to_string(),
+                    (true, 0) => batch.to_string(),
+                    (true, _) => length.to_string(),
+                })
+                .collect();
+
+            format!("{}:{}", name, shape.join("x"))
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn build_coreml(
+    config: &ExecutionProviderConfig,
+) -> Result<CoreMLExecutionProvider, Sbv2CoreError> {
+    check_options(config, &["cpu_only", "ane_only", "subgraphs"])?;
+
+    let mut coreml = CoreMLExecutionProvider::default();
+
+    if parse_option::<bool>(config, "cpu_only")? == Some(true) {
+        coreml = coreml.with_cpu_only();
+    }
+    if parse_option::<bool>(config, "ane_only")? == Some(true) {
+        coreml = coreml.with_ane_only();
+    }
+    if parse_option::<bool>(config, "subgraphs")? == Some(true) {
+        coreml = coreml.with_subgraphs();
+    }
+
+    Ok(coreml)
+}
+
+// 対応していないオプションが指定されていればエラーにする
+fn check_options(config: &ExecutionProviderConfig, known: &[&str]) -> Result<(), Sbv2CoreError> {
+    let mut unknown: Vec<&str> = config
+        .options
+        .keys()
+        .map(|key| key.as_str())
+        .filter(|key| !known.contains(key))
+        .collect();
+
+    if unknown.is_empty() {
+        return Ok(());
+    }
+
+    unknown.sort();
+    Err(Sbv2CoreError::ExecutionProviderError(format!(
+        "unknown options for {:?}: {}",
+        config.kind,
+        unknown.join(", ")
+    )))
+}
+
+fn parse_option<T>(config: &ExecutionProviderConfig, key: &str) -> Result<Option<T>, Sbv2CoreError>
+where
+    T: std::str::FromStr,
+{
+    config
+        .options
+        .get(key)
+        .map(|value| value.parse().map_err(|_| invalid_option(config, key)))
+        .transpose()
+}
+
+fn invalid_option(config: &ExecutionProviderConfig, key: &str) -> Sbv2CoreError {
+    Sbv2CoreError::ExecutionProviderError(format!(
+        "invalid value for {:?} option `{}`: {}",
+        config.kind,
+        key,
+        config
+            .options
+            .get(key)
+            .map(String::as_str)
+            .unwrap_or_default()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_known_options() {
+        let config = ExecutionProviderConfig::new(ExecutionProviderKind::Cuda)
+            .with_option("tf32", "true")
+            .with_option("gpu_mem_limit", "1024");
+
+        assert!(check_options(&config, &["tf32", "gpu_mem_limit"]).is_ok());
+        assert_eq!(parse_option::<bool>(&config, "tf32").unwrap(), Some(true));
+        assert_eq!(
+            parse_option::<usize>(&config, "gpu_mem_limit").unwrap(),
+            Some(1024)
+        );
+        assert_eq!(parse_option::<bool>(&config, "fp16").unwrap(), None);
+    }
+
+    #[test]
+    fn reject_invalid_options() {
+        let config = ExecutionProviderConfig::new(ExecutionProviderKind::Cuda)
+            .with_option("tf32", "yes")
+            .with_option("fp16", "true");
+
+        assert!(check_options(&config, &["tf32"]).is_err());
+        assert!(parse_option::<bool>(&config, "tf32").is_err());
+    }
+
+    #[test]
+    fn reject_providers_after_cpu() {
+        let providers = |kinds: &[ExecutionProviderKind]| -> Vec<ExecutionProviderConfig> {
+            kinds
+                .iter()
+                .map(|kind| ExecutionProviderConfig::new(*kind))
+                .collect()
+        };
+
+        assert!(check_cpu_is_last(&[]).is_ok());
+        assert!(check_cpu_is_last(&providers(&[
+            ExecutionProviderKind::Cuda,
+            ExecutionProviderKind::Cpu
+        ]))
+        .is_ok());
+
+        let err = check_cpu_is_last(&providers(&[
+            ExecutionProviderKind::Cpu,
+            ExecutionProviderKind::Cuda,
+        ]))
+        .unwrap_err();
+        assert!(err.to_string().contains("Cuda"), "{}", err);
+    }
+
+    // CUDA を使えないビルドでのみ確認する
+    #[test]
+    #[cfg(not(any(feature = "cuda", feature = "tensorrt", feature = "dynamic")))]
+    fn fallback_when_provider_is_unavailable() {
+        let providers = [ExecutionProviderConfig::new(ExecutionProviderKind::Cuda)];
+
+        let builder = ort::Session::builder().unwrap();
+        let registered = register_execution_providers(
+            &builder,
+            &providers,
+            ProviderFallback::Next,
+            true,
+            false,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(registered, [ExecutionProviderKind::Cpu]);
+
+        let builder = ort::Session::builder().unwrap();
+        let err = register_execution_providers(
+            &builder,
+            &providers,
+            ProviderFallback::Error,
+            true,
+            false,
+            &[],
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Cuda"), "{}", err);
+    }
+
+    fn inputs(inputs: &[(&str, &[i64])]) -> Vec<(String, Vec<i64>)> {
+        inputs
+            .iter()
+            .map(|(name, dimensions)| (name.to_string(), dimensions.to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn derive_tensorrt_profile() {
+        let config = ExecutionProviderConfig::new(ExecutionProviderKind::TensorRT)
+            .with_option("profile_max_length", "256")
+            .with_option("profile_max_batch", "8");
+
+        let bert = inputs(&[("input_ids", &[1, -1]), ("attention_mask", &[1, -1])]);
+        let (min, opt, max) = tensorrt_profile(&config, true, &bert).unwrap();
+        assert_eq!(min, "input_ids:1x1,attention_mask:1x1");
+        assert_eq!(opt, "input_ids:1x64,attention_mask:1x64");
+        assert_eq!(max, "input_ids:1x256,attention_mask:1x256");
+
+        // JP-Extra のモデル (ja_bert, en_bert がない) の次元はモデルの入力から求める
+        let vits2 = inputs(&[
+            ("x_tst", &[-1, -1]),
+            ("x_tst_lengths", &[-1]),
+            ("sid", &[-1]),
+            ("bert", &[-1, 768, -1]),
+            ("style_vec", &[-1, 128]),
+            ("sdp_ratio", &[-1]),
+            ("length_scale", &[1]),
+        ]);
+        let (_, _, max) = tensorrt_profile(&config, false, &vits2).unwrap();
+        assert_eq!(
+            max,
+            "x_tst:8x256,x_tst_lengths:8,sid:8,bert:8x768x256,style_vec:8x128,sdp_ratio:1"
+        );
+    }
+
+    #[test]
+    fn reject_invalid_tensorrt_profile() {
+        let partial = ExecutionProviderConfig::new(ExecutionProviderKind::TensorRT)
+            .with_option("profile_min_shapes", "input_ids:1x1,attention_mask:1x1");
+        assert!(tensorrt_profile(&partial, true, &[]).is_err());
+
+        let reversed = ExecutionProviderConfig::new(ExecutionProviderKind::TensorRT)
+            .with_option("profile_min_length", "100")
+            .with_option("profile_max_length", "10");
+        assert!(tensorrt_profile(&reversed, false, &[]).is_err());
+    }
+}
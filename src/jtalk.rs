@@ -156,7 +156,7 @@ impl JTalkProcess {
             let phone_len = phoneme.len() as i32;
             let word_len = token.len() as i32;
 
-            word2ph.append(&mut JTalkProcess::distribute_phone(phone_len, word_len));
+            word2ph.append(&mut crate::utils::distribute_phone(phone_len, word_len));
         }
 
         let mut new_phone_tone_list = vec![("_".to_string(), 0)];
@@ -173,21 +173,6 @@ impl JTalkProcess {
         Ok((phones, tones, new_word2ph))
     }
 
-    fn distribute_phone(n_phone: i32, n_word: i32) -> Vec<i32> {
-        let mut phones_per_word = vec![0; n_word as usize];
-
-        for _ in 0..n_phone {
-            let min_task = phones_per_word.iter().min().unwrap();
-            let min_index = phones_per_word
-                .iter()
-                .position(|&x| x == *min_task)
-                .unwrap();
-
-            phones_per_word[min_index] += 1;
-        }
-        phones_per_word
-    }
-
     fn align_tones(
         phone_with_punct: Vec<String>,
         phone_tone_list: Vec<(String, i32)>,
@@ -0,0 +1,140 @@
+use crate::nlp::Language;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Kana,
+    Han,
+    Latin,
+    // 数字・記号・空白など、前後の文字の言語に含めるもの
+    Neutral,
+}
+
+fn script_of(c: char) -> Script {
+    match c {
+        '\u{3040}'..='\u{309F}' | '\u{30A0}'..='\u{30FF}' | '\u{FF66}'..='\u{FF9F}' => Script::Kana,
+        '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '\u{3005}' => Script::Han,
+        'A'..='Z' | 'a'..='z' | 'Ａ'..='Ｚ' | 'ａ'..='ｚ' => Script::Latin,
+        _ => Script::Neutral,
+    }
+}
+
+/// テキストを日本語・英語・中国語の区間に分割する
+///
+/// 仮名を含む区間は日本語、ラテン文字の区間は英語とし、漢字のみの区間は `default` が
+/// 中国語なら中国語、それ以外なら日本語とする
+pub fn segment(text: &str, default: Language) -> Vec<(Language, String)> {
+    let han_language = match default {
+        Language::Zh => Language::Zh,
+        _ => Language::Jp,
+    };
+
+    // 仮名と漢字はまとめて CJK の区間として扱う
+    let mut runs: Vec<(Option<Script>, String)> = vec![];
+    for c in text.chars() {
+        let script = match script_of(c) {
+            Script::Han | Script::Kana => Some(Script::Han),
+            Script::Latin => Some(Script::Latin),
+            Script::Neutral => None,
+        };
+
+        match runs.last_mut() {
+            Some((last, run)) if script.is_none() || *last == script || last.is_none() => {
+                if last.is_none() {
+                    *last = script;
+                }
+                run.push(c);
+            }
+            _ => runs.push((script, c.to_string())),
+        }
+    }
+
+    let mut segments: Vec<(Language, String)> = vec![];
+    for (script, run) in runs {
+        let language = match script {
+            Some(Script::Latin) => Language::En,
+            _ if run.chars().any(|c| script_of(c) == Script::Kana) => Language::Jp,
+            Some(_) => han_language,
+            // 記号のみのテキスト
+            None => default,
+        };
+
+        match segments.last_mut() {
+            Some((last, last_run)) if *last == language => last_run.push_str(&run),
+            _ => segments.push((language, run)),
+        }
+    }
+
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_segments(text: &str, default: Language, expected: &[(Language, &str)]) {
+        let segments = segment(text, default);
+        let segments: Vec<(Language, &str)> = segments
+            .iter()
+            .map(|(language, run)| (*language, run.as_str()))
+            .collect();
+
+        assert_eq!(segments, expected);
+    }
+
+    #[test]
+    fn segment_japanese_and_english() {
+        assert_segments(
+            "今日はHelloです",
+            Language::Jp,
+            &[
+                (Language::Jp, "今日は"),
+                (Language::En, "Hello"),
+                (Language::Jp, "です"),
+            ],
+        );
+    }
+
+    #[test]
+    fn segment_kanji_only_run_by_default_language() {
+        assert_segments(
+            "Hello 世界",
+            Language::Zh,
+            &[(Language::En, "Hello "), (Language::Zh, "世界")],
+        );
+        assert_segments(
+            "Hello 世界",
+            Language::Jp,
+            &[(Language::En, "Hello "), (Language::Jp, "世界")],
+        );
+        // 仮名を含む区間は中国語にしない
+        assert_segments(
+            "我是学生です",
+            Language::Zh,
+            &[(Language::Jp, "我是学生です")],
+        );
+    }
+
+    #[test]
+    fn segment_attaches_digits_and_punctuation() {
+        // 先頭の数字は後ろの区間に、それ以外は前の区間に含める
+        assert_segments(
+            "3個のapple, 100円",
+            Language::Jp,
+            &[
+                (Language::Jp, "3個の"),
+                (Language::En, "apple, 100"),
+                (Language::Jp, "円"),
+            ],
+        );
+        assert_segments("123!", Language::En, &[(Language::En, "123!")]);
+    }
+
+    #[test]
+    fn segment_halfwidth_kana() {
+        assert_segments(
+            "ｱｲｳabc",
+            Language::Zh,
+            &[(Language::Jp, "ｱｲｳ"), (Language::En, "abc")],
+        );
+    }
+}
@@ -1,16 +1,32 @@
 mod bert;
+mod chinese;
+mod config;
+mod english;
+mod english_kana;
 mod errors;
+mod execution_provider;
 mod jtalk;
+mod lang_segment;
 mod model;
+mod model_cache;
+mod model_storage;
 mod mora;
 mod nlp;
 mod norm;
 mod style;
+mod text_norm;
 mod tokenizer;
 mod tts;
 mod tts_extension;
 mod tts_util;
 mod utils;
 
+pub use execution_provider::{ExecutionProviderConfig, ExecutionProviderKind, ProviderFallback};
+pub use model::{ExecutionMode, OptimizationLevel, ParallelSynthesisConfig, SessionConfig};
+pub use model_cache::ModelCacheConfig;
+pub use model_storage::ModelStorage;
+pub use nlp::Language;
+pub use style::StyleSpec;
+pub use text_norm::TextNormalizeOptions;
 pub use tts::{SynthesizeOptions, TtsModelHolder};
 pub use tts_extension::TtsModelHolderFromPath;
@@ -1,94 +1,987 @@
-use ndarray::{array, Array1, Array2, Array3, Axis, Ix3};
-use ort::{GraphOptimizationLevel, Session};
+use std::sync::Mutex;
 
-use crate::errors::Sbv2CoreError;
+use ndarray::{array, s, Array1, Array2, Array3, ArrayView1, Axis, Ix1, Ix3};
+use ort::{GraphOptimizationLevel, Session, TensorElementType, ValueType};
 
-pub fn load_model_session<T>(model_bytes: T, is_bert: bool) -> Result<Session, Sbv2CoreError>
-where
-    T: AsRef<[u8]>,
-{
-    let mut execution_providers = vec![ort::CPUExecutionProvider::default().build()];
+use crate::{
+    errors::Sbv2CoreError,
+    execution_provider::{
+        default_execution_providers, register_execution_providers, ExecutionProviderConfig,
+        ExecutionProviderKind, ProviderFallback,
+    },
+    model_cache::ModelCacheConfig,
+    nlp::Language,
+    tts_util::ParsedText,
+};
 
-    if cfg!(feature = "tensorrt") {
-        if is_bert {
-            execution_providers.push(
-                ort::TensorRTExecutionProvider::default()
-                    .with_fp16(true)
-                    .with_profile_min_shapes("input_ids:1x1,attention_mask:1x1")
-                    .with_profile_max_shapes("input_ids:1x100,attention_mask:1x100")
-                    .with_profile_opt_shapes("input_ids:1x25,attention_mask:1x25")
-                    .build(),
-            );
+/// Parallel synthesis of split sentences on CPU
+///
+/// # Fields
+/// - `sentence_workers`: Number of sentences synthesized in parallel (one VITS2 session each)
+/// - `intra_threads`: Intra-op threads of each VITS2 session
+/// - `inter_threads`: Inter-op threads of each VITS2 session (sequential execution mode if 1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParallelSynthesisConfig {
+    pub sentence_workers: usize,
+    pub intra_threads: usize,
+    pub inter_threads: usize,
+}
+
+impl ParallelSynthesisConfig {
+    /// 物理コアを `sentence_workers` 個の session で分け合う設定
+    pub fn with_workers(sentence_workers: usize) -> Self {
+        let sentence_workers = sentence_workers.max(1);
+
+        ParallelSynthesisConfig {
+            sentence_workers,
+            intra_threads: (num_cpus::get_physical() / sentence_workers).max(1),
+            inter_threads: 1,
         }
     }
 
-    if cfg!(feature = "cuda") {
-        let mut cuda = ort::CUDAExecutionProvider::default()
-            .with_conv_algorithm_search(ort::CUDAExecutionProviderCuDNNConvAlgoSearch::Default);
+    // 合計のスレッド数がコア数を超えないように、各 session の設定を置き換える
+    pub(crate) fn session_config(&self, session_config: &SessionConfig) -> SessionConfig {
+        let execution_mode = match self.inter_threads > 1 {
+            true => ExecutionMode::Parallel,
+            false => ExecutionMode::Sequential,
+        };
 
-        if cfg!(feature = "cuda_tf32") {
-            cuda = cuda.with_tf32(true);
+        SessionConfig {
+            intra_threads: self.intra_threads,
+            inter_threads: self.inter_threads,
+            execution_mode,
+            ..session_config.clone()
         }
+    }
+}
 
-        execution_providers.push(cuda.build());
+impl Default for ParallelSynthesisConfig {
+    fn default() -> Self {
+        // 物理コア数を 4 スレッドずつの session に分ける
+        let physical = num_cpus::get_physical();
+        ParallelSynthesisConfig::with_workers(physical / physical.min(4))
     }
+}
+
+/// Graph optimization level of an ONNX session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptimizationLevel {
+    Disable,
+    Level1,
+    Level2,
+    Level3,
+}
 
-    if cfg!(feature = "directml") {
-        execution_providers.push(ort::DirectMLExecutionProvider::default().build());
+impl From<OptimizationLevel> for GraphOptimizationLevel {
+    fn from(level: OptimizationLevel) -> Self {
+        match level {
+            OptimizationLevel::Disable => GraphOptimizationLevel::Disable,
+            OptimizationLevel::Level1 => GraphOptimizationLevel::Level1,
+            OptimizationLevel::Level2 => GraphOptimizationLevel::Level2,
+            OptimizationLevel::Level3 => GraphOptimizationLevel::Level3,
+        }
     }
+}
 
-    if cfg!(feature = "coreml") {
-        execution_providers.push(ort::CoreMLExecutionProvider::default().build());
+/// Execution mode of an ONNX session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionMode {
+    Sequential,
+    Parallel,
+}
+
+/// ONNX session options
+///
+/// # Fields
+/// - `intra_threads`: Intra-op threads
+/// - `inter_threads`: Inter-op threads (used in parallel execution mode)
+/// - `optimization_level`: Graph optimization level
+/// - `execution_mode`: Sequential or parallel execution of graph nodes
+/// - `memory_arena`: Use the CPU memory arena
+/// - `memory_pattern`: Pre-allocate memory based on the shapes of previous runs
+/// - `denormal_as_zero`: Treat denormal floats as zero
+/// - `execution_providers`: Execution providers to register in priority order (CPU is always registered last;
+///   if listed, it must be the last entry)
+/// - `provider_fallback`: What to do when an execution provider cannot be registered
+/// - `model_cache`: On-disk cache of optimized models (`None` to optimize on every load)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionConfig {
+    pub intra_threads: usize,
+    pub inter_threads: usize,
+    pub optimization_level: OptimizationLevel,
+    pub execution_mode: ExecutionMode,
+    pub memory_arena: bool,
+    pub memory_pattern: bool,
+    pub denormal_as_zero: bool,
+    pub execution_providers: Vec<ExecutionProviderConfig>,
+    pub provider_fallback: ProviderFallback,
+    pub model_cache: Option<ModelCacheConfig>,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        SessionConfig {
+            intra_threads: num_cpus::get_physical(),
+            inter_threads: num_cpus::get_physical(),
+            optimization_level: OptimizationLevel::Level3,
+            execution_mode: ExecutionMode::Parallel,
+            memory_arena: true,
+            memory_pattern: true,
+            denormal_as_zero: false,
+            execution_providers: default_execution_providers(),
+            provider_fallback: ProviderFallback::Next,
+            model_cache: None,
+        }
     }
+}
+
+/// ONNX のモデルの session を作る
+///
+/// `model_hash` はモデルの `model_cache::model_hash` (求めていない場合は `None`)
+pub fn load_model_session<T>(
+    model_bytes: T,
+    model_hash: Option<&[u8; 32]>,
+    is_bert: bool,
+    config: &SessionConfig,
+) -> Result<(Session, ExecutionProviderKind), Sbv2CoreError>
+where
+    T: AsRef<[u8]>,
+{
+    let builder = Session::builder()?;
+    let registered = register_execution_providers(
+        &builder,
+        &config.execution_providers,
+        config.provider_fallback,
+        config.memory_arena,
+        is_bert,
+        model_bytes.as_ref(),
+    )?;
+    let execution_provider = registered[0];
+
+    let mut builder = builder
+        .with_optimization_level(config.optimization_level.into())?
+        .with_intra_threads(config.intra_threads.max(1))?
+        .with_memory_pattern(config.memory_pattern)?
+        .with_denormal_as_zero(config.denormal_as_zero)?;
+
+    builder = match config.execution_mode {
+        ExecutionMode::Sequential => builder.with_parallel_execution(false)?,
+        ExecutionMode::Parallel => builder
+            .with_parallel_execution(true)?
+            .with_inter_threads(config.inter_threads.max(1))?,
+    };
 
+    // TensorRT などのコンパイル済みのノードを含むグラフは保存できないので、CPU と CUDA のみキャッシュする
+    let cacheable = registered.iter().all(|kind| {
+        matches!(
+            kind,
+            ExecutionProviderKind::Cpu | ExecutionProviderKind::Cuda
+        )
+    });
+
+    let session = match &config.model_cache {
+        Some(cache) if cacheable => crate::model_cache::commit_cached(
+            builder,
+            model_bytes.as_ref(),
+            &model_hash
+                .copied()
+                .unwrap_or_else(|| crate::model_cache::model_hash(model_bytes.as_ref())),
+            cache,
+            &crate::model_cache::config_key(config, execution_provider, is_bert),
+        )?,
+        _ => builder.commit_from_memory(model_bytes.as_ref())?,
+    };
+
+    Ok((session, execution_provider))
+}
+
+/// モデルの入出力を調べるためだけの session (最適化も execution provider の登録もしない)
+pub fn inspect_model_session<T>(model_bytes: T) -> Result<Session, Sbv2CoreError>
+where
+    T: AsRef<[u8]>,
+{
     let session = Session::builder()?
-        .with_execution_providers(execution_providers)?
-        .with_optimization_level(GraphOptimizationLevel::Level3)?
-        .with_intra_threads(num_cpus::get_physical())?
-        .with_parallel_execution(true)?
-        .with_inter_threads(num_cpus::get_physical())?
+        .with_optimization_level(GraphOptimizationLevel::Disable)?
+        .with_intra_threads(1)?
         .commit_from_memory(model_bytes.as_ref())?;
 
     Ok(session)
 }
 
+/// モデルの入力の (名前, 形状) (動的な次元は負の値)
+pub fn input_shapes<T>(model_bytes: T) -> Result<Vec<(String, Vec<i64>)>, Sbv2CoreError>
+where
+    T: AsRef<[u8]>,
+{
+    let session = inspect_model_session(model_bytes)?;
+
+    Ok(inputs_signature(&session)
+        .into_iter()
+        .map(|(name, _, dimensions)| (name, dimensions))
+        .collect())
+}
+
+/// VITS2 の ONNX モデルの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelVariant {
+    /// JP-Extra (`bert` のみ)
+    JpExtra,
+    /// JP-Extra 以外 (`bert`, `ja_bert`, `en_bert`)
+    Multilingual,
+}
+
+impl ModelVariant {
+    /// session の入力名からモデルの種類を判定する
+    pub fn detect(session: &Session) -> Self {
+        let has_input = |name: &str| session.inputs.iter().any(|i| i.name == name);
+
+        if has_input("ja_bert") || has_input("en_bert") {
+            ModelVariant::Multilingual
+        } else {
+            ModelVariant::JpExtra
+        }
+    }
+
+    /// (noise_scale, noise_scale_w) の既定値
+    pub fn default_noise_scales(&self) -> (f32, f32) {
+        match self {
+            ModelVariant::JpExtra => (0.677, 0.8),
+            ModelVariant::Multilingual => (0.6, 0.8),
+        }
+    }
+
+    /// このモデルで合成できる言語か確認する (JP-Extra は日本語のみ)
+    pub fn check_language(
+        &self,
+        language: Language,
+        mixed_language: bool,
+    ) -> Result<(), Sbv2CoreError> {
+        if *self == ModelVariant::JpExtra && (language != Language::Jp || mixed_language) {
+            return Err(Sbv2CoreError::ValueError(
+                "JP-Extra models only support Japanese text (use a multilingual model for other languages or mixed_language)"
+                    .to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+// (名前, 型, 次元数)
+type Signature = (&'static str, TensorElementType, usize);
+
+const BERT_INPUTS: [Signature; 2] = [
+    ("input_ids", TensorElementType::Int64, 2),
+    ("attention_mask", TensorElementType::Int64, 2),
+];
+const BERT_OUTPUTS: [Signature; 1] = [("output", TensorElementType::Float32, 2)];
+const BERT_BATCH_OUTPUTS: [Signature; 1] = [("output", TensorElementType::Float32, 3)];
+
+const VITS2_INPUTS: [Signature; 11] = [
+    ("x_tst", TensorElementType::Int64, 2),
+    ("x_tst_lengths", TensorElementType::Int64, 1),
+    ("sid", TensorElementType::Int64, 1),
+    ("tones", TensorElementType::Int64, 2),
+    ("language", TensorElementType::Int64, 2),
+    ("bert", TensorElementType::Float32, 3),
+    ("style_vec", TensorElementType::Float32, 2),
+    ("sdp_ratio", TensorElementType::Float32, 1),
+    ("length_scale", TensorElementType::Float32, 1),
+    ("noise_scale", TensorElementType::Float32, 1),
+    ("noise_scale_w", TensorElementType::Float32, 1),
+];
+const VITS2_MULTILINGUAL_INPUTS: [Signature; 2] = [
+    ("ja_bert", TensorElementType::Float32, 3),
+    ("en_bert", TensorElementType::Float32, 3),
+];
+const VITS2_OUTPUTS: [Signature; 1] = [("output", TensorElementType::Float32, 3)];
+// バッチで推論する場合に文ごとの長さ (フレーム数) を得るための出力
+const VITS2_LENGTHS_OUTPUT: Signature = ("y_lengths", TensorElementType::Int64, 1);
+
+/// BERT の session の入出力が想定どおりか確認する
+pub fn validate_bert(session: &Session) -> Result<(), Sbv2CoreError> {
+    let expected_outputs = match bert_supports_batch(session) {
+        true => &BERT_BATCH_OUTPUTS,
+        false => &BERT_OUTPUTS,
+    };
+
+    let mut mismatches = check_signature("input", &inputs_signature(session), &BERT_INPUTS);
+    mismatches.extend(check_signature(
+        "output",
+        &outputs_signature(session),
+        expected_outputs,
+    ));
+
+    match mismatches.is_empty() {
+        true => Ok(()),
+        false => Err(Sbv2CoreError::ModelSignatureError(mismatches)),
+    }
+}
+
+/// BERT の出力にバッチの次元があるか (`[batch, tokens, hidden]`)
+///
+/// 標準の export (`[tokens, hidden]`) では `false` になり、`bert::predict_batch` は一文ずつ推論する
+pub fn bert_supports_batch(session: &Session) -> bool {
+    outputs_signature(session)
+        .iter()
+        .any(|(name, _, dimensions)| name == "output" && dimensions.len() == 3)
+}
+
+/// VITS2 の session の入出力と、スタイルベクトルの次元が想定どおりか確認する
+pub fn validate_vits2(
+    session: &Session,
+    variant: ModelVariant,
+    style_vectors: &Array2<f32>,
+) -> Result<(), Sbv2CoreError> {
+    let mut expected_inputs = VITS2_INPUTS.to_vec();
+    if variant == ModelVariant::Multilingual {
+        expected_inputs.extend(VITS2_MULTILINGUAL_INPUTS);
+    }
+
+    let inputs = inputs_signature(session);
+    let mut mismatches = check_signature("input", &inputs, &expected_inputs);
+    mismatches.extend(check_signature(
+        "output",
+        &outputs_signature(session),
+        &VITS2_OUTPUTS,
+    ));
+
+    // 動的な次元 (-1) の場合は確認しない
+    if let Some((_, _, dimensions)) = inputs.iter().find(|(name, _, _)| name == "style_vec") {
+        if let Some(&width) = dimensions.get(1) {
+            if width > 0 && width as usize != style_vectors.ncols() {
+                mismatches.push(format!(
+                    "style vectors have width {} but input `style_vec` expects {}",
+                    style_vectors.ncols(),
+                    width
+                ));
+            }
+        }
+    }
+
+    match mismatches.is_empty() {
+        true => Ok(()),
+        false => Err(Sbv2CoreError::ModelSignatureError(mismatches)),
+    }
+}
+
+fn inputs_signature(session: &Session) -> Vec<(String, Option<TensorElementType>, Vec<i64>)> {
+    session
+        .inputs
+        .iter()
+        .map(|i| value_signature(&i.name, &i.input_type))
+        .collect()
+}
+
+fn outputs_signature(session: &Session) -> Vec<(String, Option<TensorElementType>, Vec<i64>)> {
+    session
+        .outputs
+        .iter()
+        .map(|o| value_signature(&o.name, &o.output_type))
+        .collect()
+}
+
+// テンソル以外の場合は型を None にする
+fn value_signature(
+    name: &str,
+    value_type: &ValueType,
+) -> (String, Option<TensorElementType>, Vec<i64>) {
+    match value_type {
+        ValueType::Tensor { ty, dimensions, .. } => {
+            (name.to_string(), Some(*ty), dimensions.clone())
+        }
+        _ => (name.to_string(), None, vec![]),
+    }
+}
+
+fn check_signature(
+    kind: &str,
+    actual: &[(String, Option<TensorElementType>, Vec<i64>)],
+    expected: &[Signature],
+) -> Vec<String> {
+    let mut mismatches = vec![];
+
+    for (name, ty, rank) in expected {
+        let Some((_, actual_ty, dimensions)) = actual.iter().find(|(n, _, _)| n == name) else {
+            mismatches.push(format!("missing {} `{}`", kind, name));
+            continue;
+        };
+
+        match actual_ty {
+            Some(actual_ty) if actual_ty == ty => (),
+            Some(actual_ty) => mismatches.push(format!(
+                "{} `{}` has type {:?}, expected {:?}",
+                kind, name, actual_ty, ty
+            )),
+            None => mismatches.push(format!("{} `{}` is not a tensor", kind, name)),
+        }
+
+        if actual_ty.is_some() && dimensions.len() != *rank {
+            mismatches.push(format!(
+                "{} `{}` has rank {}, expected {}",
+                kind,
+                name,
+                dimensions.len(),
+                rank
+            ));
+        }
+    }
+
+    // 余分な出力は無視できるが、余分な入力があると実行できない
+    for (name, _, _) in actual.iter().filter(|_| kind == "input") {
+        if !expected.iter().any(|(n, _, _)| n == name) {
+            mismatches.push(format!("unexpected {} `{}`", kind, name));
+        }
+    }
+
+    mismatches
+}
+
+/// 文によらない VITS2 の推論の設定
+///
+/// # Fields
+/// - `speaker_id`: Speaker ID
+/// - `style_vector`: Style vector
+/// - `sdp_ratio`: SDP ratio
+/// - `length_scale`: Length scale
+/// - `noise_scale`: Noise scale
+/// - `noise_scale_w`: Noise scale of the duration predictor
+pub struct SynthesisParams {
+    pub speaker_id: i64,
+    pub style_vector: Array1<f32>,
+    pub sdp_ratio: f32,
+    pub length_scale: f32,
+    pub noise_scale: f32,
+    pub noise_scale_w: f32,
+}
+
 pub fn synthesize(
     session: &Session,
-    bert_ori: Array2<f32>,
-    x_tst: Array1<i64>,
-    sid: Array1<i64>,
-    tones: Array1<i64>,
-    lang_ids: Array1<i64>,
-    style_vector: Array1<f32>,
-    sdp_ratio: f32,
-    length_scale: f32,
-    noise_scale: f32,
-    noise_scale_w: f32,
+    variant: ModelVariant,
+    parsed: ParsedText,
+    params: &SynthesisParams,
 ) -> Result<Array3<f32>, Sbv2CoreError> {
-    let bert = bert_ori.insert_axis(Axis(0));
-    let x_tst_lengths: Array1<i64> = array![x_tst.shape()[0] as i64];
-    let x_tst = x_tst.insert_axis(Axis(0));
-    let lang_ids = lang_ids.insert_axis(Axis(0));
-    let tones = tones.insert_axis(Axis(0));
-    let style_vector = style_vector.insert_axis(Axis(0));
-    let outputs = session.run(ort::inputs! {
-        "x_tst" => x_tst,
-        "x_tst_lengths" => x_tst_lengths,
-        "sid" => sid,
-        "tones" => tones,
-        "language" => lang_ids,
-        "bert" => bert,
-        "style_vec" => style_vector,
-        "sdp_ratio" => array![sdp_ratio],
-        "length_scale" => array![length_scale],
-        "noise_scale" => array![noise_scale],
-        "noise_scale_w" => array![noise_scale_w]
-    }?)?;
+    let (bert_ori, x_tst, tones, lang_ids) = parsed;
+
+    let (bert, ja_bert, en_bert) = match variant {
+        ModelVariant::JpExtra => (bert_ori, None, None),
+        ModelVariant::Multilingual => {
+            let (zh, ja, en) = split_bert_by_language(&bert_ori, &lang_ids);
+            (zh, Some(ja), Some(en))
+        }
+    };
+
+    let inputs = BatchInputs {
+        x_tst_lengths: array![x_tst.shape()[0] as i64],
+        x_tst: x_tst.insert_axis(Axis(0)),
+        sid: array![params.speaker_id],
+        tones: tones.insert_axis(Axis(0)),
+        lang_ids: lang_ids.insert_axis(Axis(0)),
+        bert: bert.insert_axis(Axis(0)),
+        ja_bert: ja_bert.map(|x| x.insert_axis(Axis(0))),
+        en_bert: en_bert.map(|x| x.insert_axis(Axis(0))),
+        style_vector: params.style_vector.clone().insert_axis(Axis(0)),
+    };
+
+    let (audio_array, _) = run(session, inputs, params)?;
+
+    Ok(audio_array)
+}
+
+// 文ごとに値を持つ (最初の次元がバッチの) VITS2 の入力
+const VITS2_BATCH_INPUTS: [&str; 9] = [
+    "x_tst",
+    "x_tst_lengths",
+    "sid",
+    "tones",
+    "language",
+    "bert",
+    "ja_bert",
+    "en_bert",
+    "style_vec",
+];
+
+/// 複数の文をまとめて推論できるか
+///
+/// 文ごとに値を持つ入力の最初の次元がすべて動的なモデルのみ対応する
+pub fn vits2_supports_batch(session: &Session) -> bool {
+    has_dynamic_batch(&inputs_signature(session))
+}
+
+fn has_dynamic_batch(inputs: &[(String, Option<TensorElementType>, Vec<i64>)]) -> bool {
+    let batch_inputs: Vec<_> = inputs
+        .iter()
+        .filter(|(name, _, _)| VITS2_BATCH_INPUTS.contains(&name.as_str()))
+        .collect();
+
+    !batch_inputs.is_empty()
+        && batch_inputs
+            .iter()
+            .all(|(_, _, dimensions)| dimensions.first().is_some_and(|dim| *dim < 0))
+}
+
+/// 複数の文をパディングして一度に推論し、文ごとの音声を返す
+///
+/// 文ごとの音声は、モデルが `y_lengths` を出力する場合はそのフレーム数の長さに、
+/// それ以外の場合は末尾の無音を取り除いた長さに切り出す。
+/// バッチに対応していないモデル (`vits2_supports_batch`) の場合は一文ずつ推論する
+pub fn synthesize_batch(
+    session: &Session,
+    variant: ModelVariant,
+    parsed: Vec<ParsedText>,
+    params: &SynthesisParams,
+) -> Result<Vec<Array3<f32>>, Sbv2CoreError> {
+    if parsed.len() <= 1 || !vits2_supports_batch(session) {
+        return parsed
+            .into_iter()
+            .map(|parsed| synthesize(session, variant, parsed, params))
+            .collect();
+    }
+
+    let batch_size = parsed.len();
+    let max_len = parsed
+        .iter()
+        .map(|(_, phones, _, _)| phones.len())
+        .max()
+        .unwrap_or(0);
+    let bert_dim = parsed[0].0.nrows();
+
+    // パディングした部分は x_tst_lengths でマスクされる
+    let mut x_tst = Array2::<i64>::zeros((batch_size, max_len));
+    let mut tones = Array2::<i64>::zeros((batch_size, max_len));
+    let mut lang_ids = Array2::<i64>::zeros((batch_size, max_len));
+    let mut bert = Array3::<f32>::zeros((batch_size, bert_dim, max_len));
+    let (mut ja_bert, mut en_bert) = match variant {
+        ModelVariant::JpExtra => (None, None),
+        ModelVariant::Multilingual => (Some(bert.clone()), Some(bert.clone())),
+    };
+
+    let mut lengths = vec![];
+    for (i, (bert_ori, p, t, l)) in parsed.iter().enumerate() {
+        let len = p.len();
+        lengths.push(len as i64);
+
+        x_tst.slice_mut(s![i, ..len]).assign(p);
+        tones.slice_mut(s![i, ..len]).assign(t);
+        lang_ids.slice_mut(s![i, ..len]).assign(l);
+
+        match (&mut ja_bert, &mut en_bert) {
+            (Some(ja_bert), Some(en_bert)) => {
+                let (zh, ja, en) = split_bert_by_language(bert_ori, l);
+                bert.slice_mut(s![i, .., ..len]).assign(&zh);
+                ja_bert.slice_mut(s![i, .., ..len]).assign(&ja);
+                en_bert.slice_mut(s![i, .., ..len]).assign(&en);
+            }
+            _ => bert.slice_mut(s![i, .., ..len]).assign(bert_ori),
+        }
+    }
+
+    let style_vector = Array2::from_shape_fn((batch_size, params.style_vector.len()), |(_, j)| {
+        params.style_vector[j]
+    });
+
+    let inputs = BatchInputs {
+        x_tst,
+        x_tst_lengths: Array1::from_vec(lengths),
+        sid: Array1::from_elem(batch_size, params.speaker_id),
+        tones,
+        lang_ids,
+        bert,
+        ja_bert,
+        en_bert,
+        style_vector,
+    };
+
+    let (audio_array, y_lengths) = run(session, inputs, params)?;
+    if audio_array.shape()[0] != batch_size {
+        return Err(Sbv2CoreError::ValueError(format!(
+            "VITS2 output has batch size {}, expected {}",
+            audio_array.shape()[0],
+            batch_size
+        )));
+    }
+
+    let ends = match y_lengths {
+        Some(y_lengths) => sample_ends(&y_lengths.to_vec(), audio_array.shape()[2], batch_size)?,
+        None => audio_array
+            .outer_iter()
+            .map(|audio| trailing_silence_start(audio.index_axis(Axis(0), 0)))
+            .collect(),
+    };
+
+    // 文ごとの長さより後ろはパディングなので取り除く
+    let audios = audio_array
+        .outer_iter()
+        .zip(ends)
+        .map(|(audio, end)| audio.slice(s![.., ..end]).to_owned().insert_axis(Axis(0)))
+        .collect();
+
+    Ok(audios)
+}
+
+// 文ごとのフレーム数 (`y_lengths`) から、文ごとの音声の終わりのサンプル位置を求める
+//
+// 出力はいちばん長い文のフレーム数に合わせてパディングされているので、
+// フレーム数の比で切り出す (いちばん長い文は出力の長さ全体になる)
+fn sample_ends(
+    y_lengths: &[i64],
+    total_samples: usize,
+    batch_size: usize,
+) -> Result<Vec<usize>, Sbv2CoreError> {
+    if y_lengths.len() != batch_size || y_lengths.iter().any(|frames| *frames < 0) {
+        return Err(Sbv2CoreError::ValueError(format!(
+            "invalid VITS2 output `{}`: {:?}",
+            VITS2_LENGTHS_OUTPUT.0, y_lengths
+        )));
+    }
+
+    let max_frames = y_lengths.iter().copied().max().unwrap_or(0) as usize;
+    // すべての文が 0 フレームの場合
+    if max_frames == 0 {
+        return Ok(vec![0; batch_size]);
+    }
+
+    Ok(y_lengths
+        .iter()
+        .map(|frames| *frames as usize * total_samples / max_frames)
+        .collect())
+}
+
+// パディングした部分 (マスクされたフレーム) の出力とみなす振幅
+const SILENCE_THRESHOLD: f32 = 1e-3;
+
+// 末尾の無音 (パディングした部分) が始まるサンプル位置
+fn trailing_silence_start(samples: ArrayView1<f32>) -> usize {
+    samples
+        .iter()
+        .rposition(|x| x.abs() > SILENCE_THRESHOLD)
+        .map_or(0, |i| i + 1)
+}
+
+/// 文ごとに別の session で並列に推論し、文の順に音声を返す
+pub fn synthesize_parallel(
+    sessions: &[&Session],
+    variant: ModelVariant,
+    parsed: Vec<ParsedText>,
+    params: &SynthesisParams,
+) -> Result<Vec<Array3<f32>>, Sbv2CoreError> {
+    let sentence_count = parsed.len();
+    let queue = Mutex::new(parsed.into_iter().enumerate());
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    std::thread::scope(|scope| {
+        for session in sessions {
+            let queue = &queue;
+            let sender = sender.clone();
+
+            scope.spawn(move || loop {
+                let Some((i, parsed)) = queue.lock().unwrap().next() else {
+                    break;
+                };
+
+                let audio = synthesize(session, variant, parsed, params);
+
+                if sender.send((i, audio)).is_err() {
+                    break;
+                }
+            });
+        }
+    });
+    drop(sender);
+
+    let mut audios: Vec<Option<Array3<f32>>> = vec![None; sentence_count];
+    for (i, audio) in receiver {
+        audios[i] = Some(audio?);
+    }
+
+    Ok(audios.into_iter().flatten().collect())
+}
+
+// バッチの次元を含む VITS2 の入力
+struct BatchInputs {
+    x_tst: Array2<i64>,
+    x_tst_lengths: Array1<i64>,
+    sid: Array1<i64>,
+    tones: Array2<i64>,
+    lang_ids: Array2<i64>,
+    bert: Array3<f32>,
+    ja_bert: Option<Array3<f32>>,
+    en_bert: Option<Array3<f32>>,
+    style_vector: Array2<f32>,
+}
+
+fn run(
+    session: &Session,
+    inputs: BatchInputs,
+    params: &SynthesisParams,
+) -> Result<(Array3<f32>, Option<Array1<i64>>), Sbv2CoreError> {
+    let BatchInputs {
+        x_tst,
+        x_tst_lengths,
+        sid,
+        tones,
+        lang_ids,
+        bert,
+        ja_bert,
+        en_bert,
+        style_vector,
+    } = inputs;
+
+    let inputs = match (ja_bert, en_bert) {
+        (Some(ja_bert), Some(en_bert)) => ort::inputs! {
+            "x_tst" => x_tst,
+            "x_tst_lengths" => x_tst_lengths,
+            "sid" => sid,
+            "tones" => tones,
+            "language" => lang_ids,
+            "bert" => bert,
+            "ja_bert" => ja_bert,
+            "en_bert" => en_bert,
+            "style_vec" => style_vector,
+            "sdp_ratio" => array![params.sdp_ratio],
+            "length_scale" => array![params.length_scale],
+            "noise_scale" => array![params.noise_scale],
+            "noise_scale_w" => array![params.noise_scale_w]
+        }?,
+        _ => ort::inputs! {
+            "x_tst" => x_tst,
+            "x_tst_lengths" => x_tst_lengths,
+            "sid" => sid,
+            "tones" => tones,
+            "language" => lang_ids,
+            "bert" => bert,
+            "style_vec" => style_vector,
+            "sdp_ratio" => array![params.sdp_ratio],
+            "length_scale" => array![params.length_scale],
+            "noise_scale" => array![params.noise_scale],
+            "noise_scale_w" => array![params.noise_scale_w]
+        }?,
+    };
+    let outputs = session.run(inputs)?;
 
     let audio_array = outputs["output"]
         .try_extract_tensor::<f32>()?
         .into_dimensionality::<Ix3>()?
         .to_owned();
 
-    Ok(audio_array)
+    // 文ごとの長さ (フレーム数) を出力するモデルのみ
+    let y_lengths = match outputs.get(VITS2_LENGTHS_OUTPUT.0) {
+        Some(y_lengths) => Some(
+            y_lengths
+                .try_extract_tensor::<i64>()?
+                .into_dimensionality::<Ix1>()?
+                .to_owned(),
+        ),
+        None => None,
+    };
+
+    Ok((audio_array, y_lengths))
+}
+
+// 音素ごとの言語に従って BERT の特徴量を (中国語, 日本語, 英語) に振り分け、それ以外は 0 にする
+fn split_bert_by_language(
+    bert_ori: &Array2<f32>,
+    lang_ids: &Array1<i64>,
+) -> (Array2<f32>, Array2<f32>, Array2<f32>) {
+    let mut zh = Array2::zeros(bert_ori.raw_dim());
+    let mut ja = Array2::zeros(bert_ori.raw_dim());
+    let mut en = Array2::zeros(bert_ori.raw_dim());
+
+    let len = lang_ids.len();
+    for j in 0..bert_ori.shape()[1] {
+        // intersperse で音素の間と末尾に挟んだ 0 は言語 ID も 0 (中国語) になっているので、
+        // 直前の音素の言語を使う (音素は偶数番目で、末尾は `_, 0, 0` となっている)
+        let phone = if j % 2 == 1 {
+            j - 1
+        } else if j + 1 == len && j >= 2 {
+            j - 2
+        } else {
+            j
+        };
+        let lang_id = lang_ids[phone];
+
+        let target = match lang_id {
+            id if id == Language::Zh.lang_id() => &mut zh,
+            id if id == Language::En.lang_id() => &mut en,
+            _ => &mut ja,
+        };
+        target.column_mut(j).assign(&bert_ori.column(j));
+    }
+
+    (zh, ja, en)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jp_extra_accepts_only_japanese() {
+        assert!(ModelVariant::JpExtra
+            .check_language(Language::Jp, false)
+            .is_ok());
+        assert!(ModelVariant::JpExtra
+            .check_language(Language::En, false)
+            .is_err());
+        assert!(ModelVariant::JpExtra
+            .check_language(Language::Zh, false)
+            .is_err());
+        assert!(ModelVariant::JpExtra
+            .check_language(Language::Jp, true)
+            .is_err());
+
+        for language in [Language::Jp, Language::En, Language::Zh] {
+            for mixed_language in [false, true] {
+                assert!(ModelVariant::Multilingual
+                    .check_language(language, mixed_language)
+                    .is_ok());
+            }
+        }
+    }
+
+    fn signature(
+        name: &str,
+        ty: TensorElementType,
+        rank: usize,
+    ) -> (String, Option<TensorElementType>, Vec<i64>) {
+        (name.to_string(), Some(ty), vec![-1; rank])
+    }
+
+    #[test]
+    fn check_signature_accepts_matching_model() {
+        let actual = [
+            signature("input_ids", TensorElementType::Int64, 2),
+            signature("attention_mask", TensorElementType::Int64, 2),
+        ];
+        assert!(check_signature("input", &actual, &BERT_INPUTS).is_empty());
+    }
+
+    #[test]
+    fn check_signature_reports_mismatches() {
+        let actual = [
+            signature("input_ids", TensorElementType::Float32, 2),
+            signature("attention_mask", TensorElementType::Int64, 3),
+            signature("token_type_ids", TensorElementType::Int64, 2),
+        ];
+        let mismatches = check_signature("input", &actual, &BERT_INPUTS);
+
+        assert_eq!(
+            mismatches,
+            [
+                "input `input_ids` has type Float32, expected Int64",
+                "input `attention_mask` has rank 3, expected 2",
+                "unexpected input `token_type_ids`",
+            ]
+        );
+    }
+
+    #[test]
+    fn check_signature_reports_missing_and_non_tensor() {
+        let actual = [("input_ids".to_string(), None, vec![])];
+        let mismatches = check_signature("input", &actual, &BERT_INPUTS);
+
+        assert_eq!(
+            mismatches,
+            [
+                "input `input_ids` is not a tensor",
+                "missing input `attention_mask`",
+            ]
+        );
+
+        // 余分な出力は無視する
+        let actual = [
+            signature("output", TensorElementType::Float32, 3),
+            signature("y_lengths", TensorElementType::Int64, 1),
+        ];
+        assert!(check_signature(
+            "output",
+            &actual,
+            &[("output", TensorElementType::Float32, 3)]
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn split_bert_by_language_routes_columns() {
+        let (zh, jp, en) = (
+            Language::Zh.lang_id(),
+            Language::Jp.lang_id(),
+            Language::En.lang_id(),
+        );
+        // 音素 (日本語, 日本語, 英語, 中国語) を intersperse したもの
+        let lang_ids = Array1::from(crate::utils::intersperse(&[jp, jp, en, zh], 0));
+        let bert_ori = Array2::from_shape_fn((2, lang_ids.len()), |(_, j)| j as f32 + 1.0);
+
+        let (zh_bert, ja_bert, en_bert) = split_bert_by_language(&bert_ori, &lang_ids);
+
+        let columns = |bert: &Array2<f32>| -> Vec<usize> {
+            (0..bert.ncols())
+                .filter(|j| bert.column(*j).iter().all(|x| *x != 0.0))
+                .collect()
+        };
+        assert_eq!(columns(&ja_bert), [0, 1, 2, 3]);
+        assert_eq!(columns(&en_bert), [4, 5]);
+        // 末尾の 0 も直前の中国語の音素に含める
+        assert_eq!(columns(&zh_bert), [6, 7, 8]);
+
+        // どの列もいずれか一つの言語にだけ入る
+        let sum = &zh_bert + &ja_bert + &en_bert;
+        assert_eq!(sum, bert_ori);
+    }
+
+    #[test]
+    fn dynamic_batch_detected_from_input_shapes() {
+        // 標準の export (出力は `output` のみ) でも入力のバッチの次元が動的ならバッチで推論する
+        let inputs: Vec<_> = VITS2_INPUTS
+            .iter()
+            .map(|(name, ty, rank)| signature(name, *ty, *rank))
+            .collect();
+        assert!(has_dynamic_batch(&inputs));
+
+        // バッチの次元が 1 に固定されている入力がある
+        let mut fixed = inputs.clone();
+        fixed[5].2[0] = 1;
+        assert!(!has_dynamic_batch(&fixed));
+
+        // スカラーの入力 (sdp_ratio など) は見ない
+        let mut scalars = inputs.clone();
+        scalars[7].2 = vec![1];
+        assert!(has_dynamic_batch(&scalars));
+
+        assert!(!has_dynamic_batch(&[]));
+    }
+
+    #[test]
+    fn sample_ends_scale_by_frames() {
+        // いちばん長い文は出力の長さ全体になる
+        assert_eq!(
+            sample_ends(&[10, 5, 3], 5120, 3).unwrap(),
+            [5120, 2560, 1536]
+        );
+        // 割り切れない場合も出力の長さを超えない
+        assert_eq!(sample_ends(&[3, 2], 1000, 2).unwrap(), [1000, 666]);
+    }
+
+    #[test]
+    fn sample_ends_handle_zero_length_items() {
+        assert_eq!(sample_ends(&[4, 0], 1024, 2).unwrap(), [1024, 0]);
+        assert_eq!(sample_ends(&[0, 0], 0, 2).unwrap(), [0, 0]);
+    }
+
+    #[test]
+    fn sample_ends_reject_invalid_lengths() {
+        assert!(sample_ends(&[4, -1], 1024, 2).is_err());
+        assert!(sample_ends(&[4], 1024, 2).is_err());
+    }
+
+    #[test]
+    fn trailing_silence_is_trimmed() {
+        let samples = array![0.0, 0.5, -0.2, 0.0, 0.0005, 0.0];
+        assert_eq!(trailing_silence_start(samples.view()), 3);
+
+        // すべて無音の場合は 0 サンプルになる
+        let samples = Array1::<f32>::zeros(8);
+        assert_eq!(trailing_silence_start(samples.view()), 0);
+        assert_eq!(trailing_silence_start(Array1::<f32>::zeros(0).view()), 0);
+    }
 }
@@ -0,0 +1,281 @@
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use ort::{GraphOptimizationLevel, Session, SessionBuilder};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    errors::Sbv2CoreError, execution_provider::ExecutionProviderKind, model::SessionConfig,
+};
+
+// キャッシュの形式を変えた場合に古いキャッシュを使わないようにするためのバージョン
+const CACHE_VERSION: u32 = 1;
+const CACHE_EXTENSION: &str = "onnx";
+
+/// On-disk cache of ONNX models optimized by ONNX Runtime
+///
+/// Models are keyed by the hash of the model and of the session options that affect the optimized graph,
+/// so a changed model or config uses a new entry.
+/// Only sessions running on CPU and CUDA are cached (graphs compiled by TensorRT, DirectML or CoreML cannot be saved).
+///
+/// # Fields
+/// - `dir`: Cache directory (created if missing)
+/// - `max_size`: Maximum total size of the cache in bytes, least recently used entries are removed first (`None` for no limit)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelCacheConfig {
+    pub dir: PathBuf,
+    pub max_size: Option<u64>,
+}
+
+impl ModelCacheConfig {
+    pub fn new<P>(dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        ModelCacheConfig {
+            dir: dir.into(),
+            max_size: None,
+        }
+    }
+
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Removes all cached models
+    pub fn clear(&self) -> Result<(), Sbv2CoreError> {
+        for (path, _, _) in cache_entries(&self.dir)? {
+            std::fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// キャッシュがあれば最適化済みのモデルから、なければ最適化したモデルを保存しながら session を作る
+///
+/// `model_hash` は `model_bytes` の `model_hash`
+pub(crate) fn commit_cached(
+    builder: SessionBuilder,
+    model_bytes: &[u8],
+    model_hash: &[u8; 32],
+    cache: &ModelCacheConfig,
+    config_key: &str,
+) -> Result<Session, Sbv2CoreError> {
+    std::fs::create_dir_all(&cache.dir)?;
+    let path = cache.dir.join(format!(
+        "{}.{}",
+        cache_key(model_hash, config_key),
+        CACHE_EXTENSION
+    ));
+
+    if path.exists() {
+        // 最適化済みなので再度最適化しない
+        let cached = builder
+            .clone()
+            .with_optimization_level(GraphOptimizationLevel::Disable)?
+            .commit_from_file(&path);
+
+        match cached {
+            Ok(session) => {
+                // 最近使ったものとして更新日時を更新する
+                File::options()
+                    .write(true)
+                    .open(&path)?
+                    .set_modified(SystemTime::now())?;
+                return Ok(session);
+            }
+            // 壊れているキャッシュは作り直す
+            Err(_) => std::fs::remove_file(&path)?,
+        }
+    }
+
+    // 書き込み途中のファイルを読み込まないように、一時ファイルに保存してから置き換える
+    let tmp_path = path.with_extension(format!("{}.{}.tmp", CACHE_EXTENSION, std::process::id()));
+    let session = builder
+        .with_optimized_model_path(&tmp_path)
+        .and_then(|builder| builder.commit_from_memory(model_bytes))
+        .map_err(Sbv2CoreError::from)
+        .and_then(|session| {
+            std::fs::rename(&tmp_path, &path)?;
+            Ok(session)
+        });
+
+    // 失敗した場合は書き込み途中の一時ファイルを残さない
+    let session = match session {
+        Ok(session) => session,
+        Err(e) => {
+            let _ = std::fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+    };
+
+    if let Some(max_size) = cache.max_size {
+        evict(&cache.dir, max_size, &path)?;
+    }
+
+    Ok(session)
+}
+
+/// 最適化後のグラフに影響する設定
+pub(crate) fn config_key(
+    config: &SessionConfig,
+    execution_provider: ExecutionProviderKind,
+    is_bert: bool,
+) -> String {
+    let providers: Vec<String> = config
+        .execution_providers
+        .iter()
+        .map(|provider| {
+            let mut options: Vec<_> = provider.options.iter().collect();
+            options.sort();
+            format!("{:?}:{}:{:?}", provider.kind, provider.device_id, options)
+        })
+        .collect();
+
+    format!(
+        "v{};{};{:?};{:?};{};{}",
+        CACHE_VERSION,
+        cpu_key(),
+        config.optimization_level,
+        execution_provider,
+        is_bert,
+        providers.join(",")
+    )
+}
+
+// 最適化後のグラフは CPU の命令セットに依存するので、アーキテクチャと主な拡張命令を含める
+fn cpu_key() -> String {
+    #[allow(unused_mut)]
+    let mut features: Vec<&str> = vec![];
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    for (feature, detected) in [
+        ("avx", is_x86_feature_detected!("avx")),
+        ("avx2", is_x86_feature_detected!("avx2")),
+        ("fma", is_x86_feature_detected!("fma")),
+        ("avx512f", is_x86_feature_detected!("avx512f")),
+    ] {
+        if detected {
+            features.push(feature);
+        }
+    }
+
+    format!("{}+{}", std::env::consts::ARCH, features.join("+"))
+}
+
+/// モデルの内容の SHA-256
+pub(crate) fn model_hash(model_bytes: &[u8]) -> [u8; 32] {
+    Sha256::digest(model_bytes).into()
+}
+
+fn cache_key(model_hash: &[u8; 32], config_key: &str) -> String {
+    let mut config_hasher = Sha256::new();
+    config_hasher.update(config_key.as_bytes());
+    let config_hash = config_hasher.finalize();
+
+    // モデルのハッシュ全体と設定のハッシュの先頭
+    format!("{}-{}", to_hex(model_hash), to_hex(&config_hash[..8]))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// (パス, サイズ, 更新日時)
+fn cache_entries(dir: &Path) -> Result<Vec<(PathBuf, u64, SystemTime)>, Sbv2CoreError> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut entries = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(CACHE_EXTENSION) {
+            continue;
+        }
+
+        let metadata = std::fs::metadata(&path)?;
+        entries.push((path, metadata.len(), metadata.modified()?));
+    }
+
+    Ok(entries)
+}
+
+// 合計サイズが max_size 以下になるまで古いものから削除する (keep は削除しない)
+fn evict(dir: &Path, max_size: u64, keep: &Path) -> Result<(), Sbv2CoreError> {
+    let mut entries = cache_entries(dir)?;
+    entries.sort_by_key(|(_, _, modified)| *modified);
+
+    let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+    for (path, size, _) in entries {
+        if total <= max_size {
+            break;
+        }
+        if path == keep {
+            continue;
+        }
+
+        std::fs::remove_file(&path)?;
+        total -= size;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn cache_key_depends_on_model_and_config() {
+        let key = cache_key(&model_hash(b"model"), "config");
+
+        assert_eq!(key, cache_key(&model_hash(b"model"), "config"));
+        assert_ne!(key, cache_key(&model_hash(b"model2"), "config"));
+        assert_ne!(key, cache_key(&model_hash(b"model"), "config2"));
+    }
+
+    #[test]
+    fn cpu_key_contains_arch() {
+        assert!(cpu_key().starts_with(std::env::consts::ARCH));
+    }
+
+    #[test]
+    fn evict_least_recently_used() {
+        let dir = std::env::temp_dir().join(format!("sbv2_model_cache_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let now = SystemTime::now();
+        for (i, name) in ["a", "b", "c"].iter().enumerate() {
+            let path = dir.join(format!("{}.{}", name, CACHE_EXTENSION));
+            std::fs::write(&path, [0u8; 10]).unwrap();
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(now - Duration::from_secs(10 - i as u64))
+                .unwrap();
+        }
+
+        // 最も古い a は残し、次に古い b を削除する
+        evict(&dir, 20, &dir.join("a.onnx")).unwrap();
+        let mut names: Vec<_> = cache_entries(&dir)
+            .unwrap()
+            .into_iter()
+            .map(|(path, _, _)| path.file_name().unwrap().to_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["a.onnx", "c.onnx"]);
+
+        ModelCacheConfig::new(&dir).clear().unwrap();
+        assert!(cache_entries(&dir).unwrap().is_empty());
+        std::fs::remove_dir(&dir).unwrap();
+    }
+}
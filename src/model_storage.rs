@@ -0,0 +1,295 @@
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{Cursor, Read as _},
+    path::{Path, PathBuf},
+};
+
+use memmap2::Mmap;
+
+use crate::errors::Sbv2CoreError;
+
+/// How models of a holder with `max_loaded_models` keep the VITS2 model to reload their session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModelStorage {
+    /// Keep the decompressed model bytes in memory
+    #[default]
+    Memory,
+    /// Keep the zstd-compressed model in memory (the original bytes for `.sbv2` models)
+    Compressed,
+    /// Keep only the file path and read the file again on reload
+    ///
+    /// Models not loaded from a file are kept in memory. Reloading fails if the file has changed since it was loaded.
+    Path,
+    /// Memory-map the model file (`.sbv2` files are read again on reload as with `Path`)
+    ///
+    /// Models not loaded from a file are kept in memory. Reloading fails if the file has changed since it was loaded.
+    Mmap,
+}
+
+/// モデルの読み込み元
+pub(crate) enum ModelOrigin<'a> {
+    Bytes,
+    Sbv2Bytes(&'a [u8]),
+    OnnxPath(PathBuf),
+    Sbv2Path(PathBuf, &'a [u8]),
+}
+
+/// session を読み込み直すために保持する VITS2 のモデル
+///
+/// ファイルから読み込み直す場合は、ファイルが変更されていないか確認するために VITS2 のモデルのハッシュを持つ
+#[derive(Debug)]
+pub(crate) enum ModelSource {
+    Bytes(Vec<u8>),
+    Zstd(Vec<u8>),
+    Sbv2(Vec<u8>),
+    OnnxPath(PathBuf, [u8; 32]),
+    Sbv2Path(PathBuf, [u8; 32]),
+    Mmap(Mmap, PathBuf, [u8; 32]),
+}
+
+impl ModelSource {
+    pub fn new(
+        storage: ModelStorage,
+        vits2_bytes: Vec<u8>,
+        origin: ModelOrigin,
+        model_hash: [u8; 32],
+    ) -> Result<Self, Sbv2CoreError> {
+        let source = match (storage, origin) {
+            (ModelStorage::Memory, _) => ModelSource::Bytes(vits2_bytes),
+
+            (
+                ModelStorage::Compressed,
+                ModelOrigin::Sbv2Bytes(sbv2_bytes) | ModelOrigin::Sbv2Path(_, sbv2_bytes),
+            ) => ModelSource::Sbv2(sbv2_bytes.to_vec()),
+            (ModelStorage::Compressed, _) => {
+                ModelSource::Zstd(zstd::encode_all(Cursor::new(vits2_bytes), 0)?)
+            }
+
+            (ModelStorage::Path | ModelStorage::Mmap, ModelOrigin::Sbv2Path(path, _)) => {
+                ModelSource::Sbv2Path(path, model_hash)
+            }
+            (ModelStorage::Path, ModelOrigin::OnnxPath(path)) => {
+                ModelSource::OnnxPath(path, model_hash)
+            }
+            (ModelStorage::Mmap, ModelOrigin::OnnxPath(path)) => {
+                let file = File::open(&path)?;
+                // ファイルが変更された場合は読み込み直すときにハッシュで検出する
+                let mmap = unsafe { Mmap::map(&file)? };
+                ModelSource::Mmap(mmap, path, model_hash)
+            }
+
+            // ファイルから読み込んでいないモデル
+            (ModelStorage::Path | ModelStorage::Mmap, _) => ModelSource::Bytes(vits2_bytes),
+        };
+
+        Ok(source)
+    }
+
+    pub fn read(&self) -> Result<Cow<'_, [u8]>, Sbv2CoreError> {
+        let bytes = match self {
+            ModelSource::Bytes(bytes) => Cow::Borrowed(bytes.as_slice()),
+            ModelSource::Zstd(bytes) => Cow::Owned(zstd::decode_all(Cursor::new(bytes))?),
+            ModelSource::Sbv2(bytes) => Cow::Owned(extract_sbv2(bytes)?.0),
+            ModelSource::OnnxPath(path, model_hash) => {
+                check_unchanged(path, Cow::Owned(std::fs::read(path)?), model_hash)?
+            }
+            ModelSource::Sbv2Path(path, model_hash) => {
+                let vits2_bytes = extract_sbv2(std::fs::read(path)?)?.0;
+                check_unchanged(path, Cow::Owned(vits2_bytes), model_hash)?
+            }
+            ModelSource::Mmap(mmap, path, model_hash) => {
+                // 短くなったファイルの範囲外を読まないように、先に長さを確認する
+                if std::fs::metadata(path)?.len() != mmap.len() as u64 {
+                    return Err(changed_err(path));
+                }
+                check_unchanged(path, Cow::Borrowed(&mmap[..]), model_hash)?
+            }
+        };
+
+        Ok(bytes)
+    }
+}
+
+// 読み込んだときから変更されたファイルのモデルは、検証したモデルと違う可能性があるので使わない
+fn check_unchanged<'a>(
+    path: &Path,
+    vits2_bytes: Cow<'a, [u8]>,
+    model_hash: &[u8; 32],
+) -> Result<Cow<'a, [u8]>, Sbv2CoreError> {
+    if crate::model_cache::model_hash(&vits2_bytes) != *model_hash {
+        return Err(changed_err(path));
+    }
+
+    Ok(vits2_bytes)
+}
+
+fn changed_err(path: &Path) -> Sbv2CoreError {
+    Sbv2CoreError::ValueError(format!(
+        "model file {} has changed since it was loaded",
+        path.display()
+    ))
+}
+
+// (vits2, style_vectors, config)
+type Sbv2Files = (Vec<u8>, Vec<u8>, Option<Vec<u8>>);
+
+/// .sbv2 ファイルから vits2 ファイルと style_vectors ファイル (と config.json) を取り出す
+pub(crate) fn extract_sbv2<T>(sbv2file_bytes: T) -> Result<Sbv2Files, Sbv2CoreError>
+where
+    T: AsRef<[u8]>,
+{
+    let decoded = zstd::decode_all(Cursor::new(sbv2file_bytes.as_ref()))?;
+    let mut archive = tar::Archive::new(Cursor::new(decoded));
+    let mut entries = archive.entries()?;
+
+    let mut vits2 = None;
+    let mut style_vectors = None;
+    let mut config = None;
+
+    while let Some(Ok(mut e)) = entries.next() {
+        let mut file_bytes = Vec::with_capacity(e.size() as usize);
+        e.read_to_end(&mut file_bytes)?;
+
+        let file_name = String::from_utf8_lossy(&e.path_bytes()).to_string();
+        match file_name.as_str() {
+            "model.onnx" => vits2 = Some(file_bytes),
+            "style_vectors.json"
+            | "style_vectors.npy"
+            | "style_vectors.npz"
+            | "style_vectors.safetensors" => style_vectors = Some(file_bytes),
+            "config.json" => config = Some(file_bytes),
+            _ => continue,
+        }
+    }
+
+    let create_err = |content: &str| Err(Sbv2CoreError::ModelNotFoundError(content.to_string()));
+
+    match (vits2, style_vectors) {
+        (Some(vits2), Some(style_vectors)) => Ok((vits2, style_vectors, config)),
+        (None, Some(_)) => create_err("vits2 not found"),
+        (Some(_), None) => create_err("style_vectors not found"),
+        (None, None) => create_err("vits2, style_vectors not found"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model_cache::model_hash;
+
+    use super::*;
+
+    fn sbv2(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(vec![]);
+        for (name, data) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, name, *data).unwrap();
+        }
+
+        zstd::encode_all(Cursor::new(builder.into_inner().unwrap()), 0).unwrap()
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "sbv2_model_storage_{}_{}",
+            std::process::id(),
+            name
+        ))
+    }
+
+    #[test]
+    fn compressed_round_trip() {
+        let vits2 = b"vits2 model".repeat(100);
+        let source = ModelSource::new(
+            ModelStorage::Compressed,
+            vits2.clone(),
+            ModelOrigin::Bytes,
+            model_hash(&vits2),
+        )
+        .unwrap();
+
+        assert!(matches!(&source, ModelSource::Zstd(bytes) if bytes.len() < vits2.len()));
+        assert_eq!(source.read().unwrap(), vits2.as_slice());
+    }
+
+    #[test]
+    fn read_vits2_from_sbv2() {
+        let vits2 = b"vits2 model".to_vec();
+        let sbv2_bytes = sbv2(&[
+            ("model.onnx", &vits2),
+            ("style_vectors.json", b"{}"),
+            ("config.json", b"{}"),
+        ]);
+
+        let (extracted, style_vectors, config) = extract_sbv2(&sbv2_bytes).unwrap();
+        assert_eq!(extracted, vits2);
+        assert_eq!(style_vectors, b"{}");
+        assert_eq!(config.as_deref(), Some(b"{}".as_slice()));
+
+        // .sbv2 ファイルはそのまま保持して読み込み直すときに取り出す
+        let source = ModelSource::new(
+            ModelStorage::Compressed,
+            extracted,
+            ModelOrigin::Sbv2Bytes(&sbv2_bytes),
+            model_hash(&vits2),
+        )
+        .unwrap();
+        assert!(matches!(&source, ModelSource::Sbv2(bytes) if *bytes == sbv2_bytes));
+        assert_eq!(source.read().unwrap(), vits2.as_slice());
+    }
+
+    #[test]
+    fn extract_sbv2_missing_files() {
+        let not_found = |sbv2_bytes: Vec<u8>| match extract_sbv2(sbv2_bytes) {
+            Err(Sbv2CoreError::ModelNotFoundError(content)) => content,
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        };
+
+        assert_eq!(
+            not_found(sbv2(&[("style_vectors.npy", b"")])),
+            "vits2 not found"
+        );
+        assert_eq!(
+            not_found(sbv2(&[("model.onnx", b"")])),
+            "style_vectors not found"
+        );
+        assert_eq!(
+            not_found(sbv2(&[("README.md", b"")])),
+            "vits2, style_vectors not found"
+        );
+
+        // zstd で圧縮されていない
+        assert!(matches!(
+            extract_sbv2(b"not an sbv2 file"),
+            Err(Sbv2CoreError::IoError(_))
+        ));
+    }
+
+    #[test]
+    fn reject_changed_file() {
+        for storage in [ModelStorage::Path, ModelStorage::Mmap] {
+            let path = temp_path(&format!("{:?}.onnx", storage));
+            let vits2 = b"vits2 model".to_vec();
+            std::fs::write(&path, &vits2).unwrap();
+
+            let source = ModelSource::new(
+                storage,
+                vits2.clone(),
+                ModelOrigin::OnnxPath(path.clone()),
+                model_hash(&vits2),
+            )
+            .unwrap();
+            assert_eq!(source.read().unwrap(), vits2.as_slice());
+
+            // 同じ長さの別のモデルに置き換える
+            std::fs::write(&path, b"other model").unwrap();
+            let err = source.read().unwrap_err();
+            assert!(err.to_string().contains("has changed"), "{}", err);
+
+            drop(source);
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+}
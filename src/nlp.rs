@@ -1,4 +1,4 @@
-use crate::norm::SYMBOLS;
+use crate::{errors::Sbv2CoreError, norm::SYMBOLS};
 use std::{collections::HashMap, sync::LazyLock};
 
 static SYMBOL_TO_ID: LazyLock<HashMap<String, i32>> = LazyLock::new(|| {
@@ -11,16 +11,86 @@ static SYMBOL_TO_ID: LazyLock<HashMap<String, i32>> = LazyLock::new(|| {
     map
 });
 
+/// Language of the input text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Language {
+    #[default]
+    Jp,
+    En,
+    Zh,
+}
+
+impl Language {
+    pub(crate) fn lang_id(&self) -> i64 {
+        match self {
+            Language::Zh => 0,
+            Language::Jp => 1,
+            Language::En => 2,
+        }
+    }
+
+    // 言語ごとの音調の開始位置 (ZH: 6 個, JP: 2 個, EN: 4 個の順に並んでいる)
+    pub(crate) fn tone_start(&self) -> i32 {
+        match self {
+            Language::Zh => 0,
+            Language::Jp => 6,
+            Language::En => 8,
+        }
+    }
+}
+
 pub fn cleaned_text_to_sequence(
     cleaned_phones: Vec<String>,
     tones: Vec<i32>,
-) -> (Vec<i64>, Vec<i64>, Vec<i64>) {
+    language: Language,
+) -> Result<(Vec<i64>, Vec<i64>, Vec<i64>), Sbv2CoreError> {
+    // 記号表にない音素は UNK にせずエラーにする
+    let unknown: Vec<&str> = cleaned_phones
+        .iter()
+        .filter(|phone| !SYMBOL_TO_ID.contains_key(phone.as_str()))
+        .map(|phone| phone.as_str())
+        .collect();
+    if !unknown.is_empty() {
+        return Err(Sbv2CoreError::ValueError(format!(
+            "unknown phones for {:?}: {}",
+            language,
+            unknown.join(", ")
+        )));
+    }
+
     let phones: Vec<i64> = cleaned_phones
         .iter()
-        .map(|phone| *SYMBOL_TO_ID.get(phone).unwrap() as i64)
+        .map(|phone| SYMBOL_TO_ID[phone.as_str()] as i64)
         .collect();
-    let tones: Vec<i64> = tones.iter().map(|tone| (*tone + 6) as i64).collect();
-    let lang_ids: Vec<i64> = vec![1; phones.len()];
+    let tones: Vec<i64> = tones
+        .iter()
+        .map(|tone| (*tone + language.tone_start()) as i64)
+        .collect();
+    let lang_ids: Vec<i64> = vec![language.lang_id(); phones.len()];
+
+    Ok((phones, tones, lang_ids))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    (phones, tones, lang_ids)
+    #[test]
+    fn reject_unknown_phones() {
+        let phones = vec!["a".to_string(), "xx".to_string()];
+        let result = cleaned_text_to_sequence(phones, vec![0, 0], Language::Jp);
+
+        assert!(result.unwrap_err().to_string().contains("xx"));
+    }
+
+    #[test]
+    fn map_known_phones() {
+        let phones = vec!["_".to_string(), "a".to_string()];
+        let (phones, tones, lang_ids) =
+            cleaned_text_to_sequence(phones, vec![0, 1], Language::Jp).unwrap();
+
+        assert_eq!(phones[0], 0);
+        assert_eq!(tones, [6, 7]);
+        assert_eq!(lang_ids, [1, 1]);
+    }
 }
@@ -134,3 +134,43 @@ pub fn replace_punctuation(mut text: String) -> String {
 
     content
 }
+
+static EN_PUNCTUATION_CLEANUP_PATTERN: LazyLock<regex::Regex> = LazyLock::new(|| {
+    let pattern =
+        r"[^\u{0041}-\u{005A}\u{0061}-\u{007A}\s".to_owned() + &PUNCTUATIONS.join("") + r"]+";
+
+    regex::Regex::new(&pattern).unwrap()
+});
+
+static WHITESPACE_PATTERN: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"\s+").unwrap());
+
+pub fn replace_punctuation_en(mut text: String) -> String {
+    // 英語のテキストは単語の区切りの空白を残す
+    for (k, v) in REPLACE_MAP.iter() {
+        text = text.replace(k, v);
+    }
+
+    let content = EN_PUNCTUATION_CLEANUP_PATTERN.replace_all(&text, "");
+
+    WHITESPACE_PATTERN
+        .replace_all(&content, " ")
+        .trim()
+        .to_string()
+}
+
+static ZH_PUNCTUATION_CLEANUP_PATTERN: LazyLock<regex::Regex> = LazyLock::new(|| {
+    let pattern = r"[^\u{4E00}-\u{9FA5}".to_owned() + &PUNCTUATIONS.join("") + r"]+";
+
+    regex::Regex::new(&pattern).unwrap()
+});
+
+pub fn replace_punctuation_zh(mut text: String) -> String {
+    for (k, v) in REPLACE_MAP.iter() {
+        text = text.replace(k, v);
+    }
+
+    ZH_PUNCTUATION_CLEANUP_PATTERN
+        .replace_all(&text, "")
+        .to_string()
+}
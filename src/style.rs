@@ -1,19 +1,68 @@
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read as _},
+    sync::LazyLock,
+};
+
 use ndarray::{s, Array1, Array2};
-use serde::Deserialize;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 
 use crate::errors::Sbv2CoreError;
 
-#[derive(Deserialize)]
+static NPY_DESCR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"'descr'\s*:\s*'([^']*)'").unwrap());
+static NPY_FORTRAN_ORDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"'fortran_order'\s*:\s*(True|False)").unwrap());
+static NPY_SHAPE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"'shape'\s*:\s*\(([^)]*)\)").unwrap());
+
+#[derive(Deserialize, Serialize)]
 pub struct Data {
     pub shape: [usize; 2],
     pub data: Vec<Vec<f32>>,
 }
 
+/// スタイルベクトルの形式 (JSON, NPY, NPZ, safetensors) を判定して読み込む
 pub fn load_style<T>(style: T) -> Result<Array2<f32>, Sbv2CoreError>
 where
     T: AsRef<[u8]>,
 {
-    let data: Data = serde_json::from_slice(style.as_ref())?;
+    let style = style.as_ref();
+
+    if style.starts_with(b"\x93NUMPY") {
+        load_style_npy(style)
+    } else if style.starts_with(b"PK\x03\x04") {
+        load_style_npz(style)
+    } else if is_safetensors(style) {
+        load_style_safetensors(style)
+    } else {
+        load_style_json(style)
+    }
+}
+
+// safetensors は 8 バイトのヘッダー長の後に JSON のヘッダーが続く
+// (JSON のテキストをヘッダー長として読むとファイルより大きくなる)
+fn is_safetensors(style: &[u8]) -> bool {
+    if style.len() <= 8 || style[8] != b'{' {
+        return false;
+    }
+
+    let header_len = u64::from_le_bytes(style[..8].try_into().unwrap());
+    header_len <= (style.len() - 8) as u64
+}
+
+fn load_style_json(style: &[u8]) -> Result<Array2<f32>, Sbv2CoreError> {
+    let data: Data = serde_json::from_slice(style)?;
+
+    // 行ごとの長さが違っても合計が一致すると読み込めてしまうので先に確認する
+    let [rows, cols] = data.shape;
+    if data.data.len() != rows || data.data.iter().any(|row| row.len() != cols) {
+        return Err(Sbv2CoreError::ValueError(format!(
+            "style vectors shape {:?} does not match data",
+            data.shape
+        )));
+    }
 
     Ok(Array2::from_shape_vec(
         data.shape,
@@ -21,14 +70,526 @@ where
     )?)
 }
 
-pub fn get_style_vector(
+// float32 (リトルエンディアン) かつ C-order の 2 次元配列のみ対応
+fn load_style_npy(style: &[u8]) -> Result<Array2<f32>, Sbv2CoreError> {
+    let create_err = |content: &str| Sbv2CoreError::ValueError(format!("npy: {}", content));
+
+    if style.len() < 10 {
+        return Err(create_err("file is too short"));
+    }
+
+    let major_version = style[6];
+    let (header_len, header_start): (usize, usize) = match major_version {
+        1 => (u16::from_le_bytes([style[8], style[9]]) as usize, 10),
+        2 | 3 if style.len() >= 12 => (
+            u32::from_le_bytes([style[8], style[9], style[10], style[11]]) as usize,
+            12,
+        ),
+        _ => {
+            return Err(create_err(&format!(
+                "unsupported version {}",
+                major_version
+            )))
+        }
+    };
+
+    let header_end = header_start
+        .checked_add(header_len)
+        .ok_or_else(|| create_err("header is truncated"))?;
+    let header = style
+        .get(header_start..header_end)
+        .ok_or_else(|| create_err("header is truncated"))?;
+    let header = String::from_utf8_lossy(header);
+
+    let descr = NPY_DESCR
+        .captures(&header)
+        .map(|caps| caps[1].to_string())
+        .ok_or_else(|| create_err("descr not found"))?;
+    if descr != "<f4" {
+        return Err(create_err(&format!("dtype {} is not float32", descr)));
+    }
+
+    if NPY_FORTRAN_ORDER
+        .captures(&header)
+        .is_some_and(|caps| &caps[1] == "True")
+    {
+        return Err(create_err("fortran order is not supported"));
+    }
+
+    let shape: Vec<usize> = NPY_SHAPE
+        .captures(&header)
+        .ok_or_else(|| create_err("shape not found"))?[1]
+        .split(',')
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .map(|x| x.parse::<usize>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| create_err("invalid shape"))?;
+
+    let [rows, cols] = shape[..] else {
+        return Err(create_err(&format!(
+            "shape {:?} is not 2-dimensional",
+            shape
+        )));
+    };
+
+    let data = &style[header_end..];
+    array_from_f32_bytes(data, rows, cols)
+}
+
+// 最初の .npy を読み込む
+fn load_style_npz(style: &[u8]) -> Result<Array2<f32>, Sbv2CoreError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(style))
+        .map_err(|e| Sbv2CoreError::ValueError(format!("npz: {}", e)))?;
+
+    for i in 0..archive.len() {
+        let mut file = archive
+            .by_index(i)
+            .map_err(|e| Sbv2CoreError::ValueError(format!("npz: {}", e)))?;
+
+        if !file.name().ends_with(".npy") {
+            continue;
+        }
+
+        // ヘッダーのサイズは信用せずに読み込む
+        let mut npy_bytes = vec![];
+        file.read_to_end(&mut npy_bytes)?;
+
+        return load_style_npy(&npy_bytes);
+    }
+
+    Err(Sbv2CoreError::ValueError(
+        "npz: no .npy file found".to_string(),
+    ))
+}
+
+#[derive(Deserialize)]
+struct SafetensorsInfo {
+    dtype: String,
+    shape: Vec<usize>,
+    data_offsets: [usize; 2],
+}
+
+// `style_vectors` という名前のテンソル、なければ最初の 2 次元のテンソルを読み込む
+fn load_style_safetensors(style: &[u8]) -> Result<Array2<f32>, Sbv2CoreError> {
+    let create_err = |content: &str| Sbv2CoreError::ValueError(format!("safetensors: {}", content));
+
+    let header_len = style
+        .get(..8)
+        .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| create_err("file is too short"))?;
+    let data_start = usize::try_from(header_len)
+        .ok()
+        .and_then(|len| len.checked_add(8))
+        .ok_or_else(|| create_err("header is truncated"))?;
+    let header = style
+        .get(8..data_start)
+        .ok_or_else(|| create_err("header is truncated"))?;
+
+    let mut tensors: HashMap<String, serde_json::Value> = serde_json::from_slice(header)?;
+    tensors.remove("__metadata__");
+
+    let mut tensors: Vec<(String, SafetensorsInfo)> = tensors
+        .into_iter()
+        .map(|(name, info)| Ok((name, serde_json::from_value(info)?)))
+        .collect::<Result<_, Sbv2CoreError>>()?;
+    tensors.sort_by_key(|(_, info)| info.data_offsets[0]);
+
+    let (_, info) = tensors
+        .iter()
+        .find(|(name, _)| name == "style_vectors")
+        .or_else(|| tensors.iter().find(|(_, info)| info.shape.len() == 2))
+        .ok_or_else(|| create_err("no 2-dimensional tensor found"))?;
+
+    if info.dtype != "F32" {
+        return Err(create_err(&format!("dtype {} is not F32", info.dtype)));
+    }
+    let [rows, cols] = info.shape[..] else {
+        return Err(create_err(&format!(
+            "shape {:?} is not 2-dimensional",
+            info.shape
+        )));
+    };
+
+    let [start, end] = info.data_offsets;
+    let data = data_start
+        .checked_add(start)
+        .zip(data_start.checked_add(end))
+        .filter(|(start, end)| start <= end)
+        .and_then(|(start, end)| style.get(start..end))
+        .ok_or_else(|| create_err("data is truncated"))?;
+
+    array_from_f32_bytes(data, rows, cols)
+}
+
+fn array_from_f32_bytes(
+    data: &[u8],
+    rows: usize,
+    cols: usize,
+) -> Result<Array2<f32>, Sbv2CoreError> {
+    let expected_len = rows.checked_mul(cols).and_then(|len| len.checked_mul(4));
+    if expected_len != Some(data.len()) {
+        return Err(Sbv2CoreError::ValueError(format!(
+            "style vectors shape [{}, {}] does not match data length {}",
+            rows,
+            cols,
+            data.len()
+        )));
+    }
+
+    let values = data
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+
+    Ok(Array2::from_shape_vec((rows, cols), values)?)
+}
+
+/// `load_style` で読み込める JSON にする
+pub fn save_style(style_vectors: &Array2<f32>) -> Result<Vec<u8>, Sbv2CoreError> {
+    let data = Data {
+        shape: [style_vectors.nrows(), style_vectors.ncols()],
+        data: style_vectors
+            .rows()
+            .into_iter()
+            .map(|row| row.to_vec())
+            .collect(),
+    };
+
+    Ok(serde_json::to_vec(&data)?)
+}
+
+/// Style to use for synthesis
+///
+/// # Variants
+/// - `Id`: A single style by ID
+/// - `Mix`: Weighted mixture of styles `(style_id, weight)` (weights are normalized)
+/// - `Lerp`: Linear interpolation `(from, to, t)` between two styles
+/// - `Slerp`: Spherical interpolation `(from, to, t)` between two styles
+/// - `CrossModelMix`: Weighted mixture of styles `(model_ident, style_id, weight)` from loaded models
+///   that share a style vector space
+#[derive(Debug, Clone, PartialEq)]
+pub enum StyleSpec {
+    Id(i32),
+    Mix(Vec<(i32, f32)>),
+    Lerp(i32, i32, f32),
+    Slerp(i32, i32, f32),
+    CrossModelMix(Vec<(String, i32, f32)>),
+}
+
+impl From<i32> for StyleSpec {
+    fn from(style_id: i32) -> Self {
+        StyleSpec::Id(style_id)
+    }
+}
+
+/// `style_vectors` の平均 (0 行目) から `style_vector` への差を `weight` 倍する
+pub fn apply_weight(
     style_vectors: &Array2<f32>,
-    style_id: i32,
+    style_vector: Array1<f32>,
     weight: f32,
 ) -> Result<Array1<f32>, Sbv2CoreError> {
-    let mean = style_vectors.slice(s![0, ..]).to_owned();
-    let style_vector = style_vectors.slice(s![style_id as usize, ..]).to_owned();
+    let mean = get_style_row(style_vectors, 0)?;
+    if mean.len() != style_vector.len() {
+        return Err(Sbv2CoreError::ValueError(format!(
+            "style vector has width {}, expected {}",
+            style_vector.len(),
+            mean.len()
+        )));
+    }
+
     let diff = (style_vector - &mean) * weight;
 
     Ok(mean + &diff)
 }
+
+/// 重み付きの平均 (重みは合計 1 に正規化する)
+pub fn mix(style_vectors: &[(Array1<f32>, f32)]) -> Result<Array1<f32>, Sbv2CoreError> {
+    let Some((first, _)) = style_vectors.first() else {
+        return Err(Sbv2CoreError::ValueError(
+            "style mix has no styles".to_string(),
+        ));
+    };
+
+    let total: f32 = style_vectors.iter().map(|(_, weight)| weight).sum();
+    if total <= 0.0 || !total.is_finite() {
+        return Err(Sbv2CoreError::ValueError(format!(
+            "style mix weights must have a positive sum, got {}",
+            total
+        )));
+    }
+
+    let mut mixed = Array1::zeros(first.len());
+    for (style_vector, weight) in style_vectors {
+        if style_vector.len() != first.len() {
+            return Err(Sbv2CoreError::ValueError(format!(
+                "style vectors have different widths ({} and {})",
+                first.len(),
+                style_vector.len()
+            )));
+        }
+        mixed = mixed + style_vector * (*weight / total);
+    }
+
+    Ok(mixed)
+}
+
+pub fn lerp(from: &Array1<f32>, to: &Array1<f32>, t: f32) -> Array1<f32> {
+    from * (1.0 - t) + to * t
+}
+
+pub fn slerp(from: &Array1<f32>, to: &Array1<f32>, t: f32) -> Array1<f32> {
+    let norm = from.dot(from).sqrt() * to.dot(to).sqrt();
+    if norm == 0.0 {
+        return lerp(from, to, t);
+    }
+
+    let omega = (from.dot(to) / norm).clamp(-1.0, 1.0).acos();
+    let sin_omega = omega.sin();
+
+    // ほぼ同じ向きの場合は線形補間にする
+    if sin_omega.abs() < 1e-6 {
+        return lerp(from, to, t);
+    }
+
+    from * (((1.0 - t) * omega).sin() / sin_omega) + to * ((t * omega).sin() / sin_omega)
+}
+
+/// 範囲を確認して `style_id` 行目のスタイルベクトルを返す
+pub fn get_style_row(
+    style_vectors: &Array2<f32>,
+    style_id: i32,
+) -> Result<Array1<f32>, Sbv2CoreError> {
+    let style_count = style_vectors.nrows();
+    if style_count == 0 {
+        return Err(Sbv2CoreError::ValueError(
+            "style vectors have no styles".to_string(),
+        ));
+    }
+    if style_id < 0 || style_id as usize >= style_count {
+        return Err(Sbv2CoreError::ValueError(format!(
+            "style id {} is out of range (valid: 0..={})",
+            style_id,
+            style_count - 1
+        )));
+    }
+
+    Ok(style_vectors.slice(s![style_id as usize, ..]).to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn style_vectors() -> Array2<f32> {
+        Array2::from_shape_vec((2, 3), vec![0.0, 0.0, 0.0, 1.0, 2.0, 3.0]).unwrap()
+    }
+
+    #[test]
+    fn get_style_row_in_range() {
+        let style_vector = get_style_row(&style_vectors(), 1).unwrap();
+        let style_vector = apply_weight(&style_vectors(), style_vector, 0.5).unwrap();
+        assert_eq!(style_vector.to_vec(), vec![0.5, 1.0, 1.5]);
+    }
+
+    #[test]
+    fn get_style_row_negative_id() {
+        let err = get_style_row(&style_vectors(), -1).unwrap_err();
+        assert!(err.to_string().contains("0..=1"), "{}", err);
+    }
+
+    #[test]
+    fn get_style_row_too_large_id() {
+        let err = get_style_row(&style_vectors(), 2).unwrap_err();
+        assert!(err.to_string().contains("0..=1"), "{}", err);
+    }
+
+    #[test]
+    fn get_style_row_no_styles() {
+        let style_vectors = Array2::<f32>::zeros((0, 3));
+        assert!(get_style_row(&style_vectors, 0).is_err());
+    }
+
+    #[test]
+    fn mix_normalizes_weights() {
+        let a = Array1::from(vec![1.0, 0.0]);
+        let b = Array1::from(vec![0.0, 1.0]);
+
+        // 重みの合計が 1 でなくても正規化される
+        let mixed = mix(&[(a.clone(), 3.0), (b.clone(), 1.0)]).unwrap();
+        assert_eq!(mixed.to_vec(), vec![0.75, 0.25]);
+
+        assert!(mix(&[(a.clone(), 0.0), (b, 0.0)]).is_err());
+        assert!(mix(&[]).is_err());
+    }
+
+    #[test]
+    fn mix_width_mismatch() {
+        // 別のモデルのスタイルベクトルの次元が違う場合
+        let a = Array1::from(vec![1.0, 0.0]);
+        let b = Array1::from(vec![0.0, 1.0, 2.0]);
+        let err = mix(&[(a, 1.0), (b, 1.0)]).unwrap_err();
+        assert!(err.to_string().contains("different widths"), "{}", err);
+
+        let err = apply_weight(&style_vectors(), Array1::from(vec![1.0, 0.0]), 1.0).unwrap_err();
+        assert!(err.to_string().contains("expected 3"), "{}", err);
+    }
+
+    #[test]
+    fn lerp_midpoint() {
+        let a = Array1::from(vec![0.0, 2.0]);
+        let b = Array1::from(vec![2.0, 4.0]);
+        assert_eq!(lerp(&a, &b, 0.5).to_vec(), vec![1.0, 3.0]);
+    }
+
+    #[test]
+    fn slerp_endpoints() {
+        let a = Array1::from(vec![1.0, 0.0]);
+        let b = Array1::from(vec![0.0, 2.0]);
+
+        let close = |x: Array1<f32>, y: &Array1<f32>| (x - y).iter().all(|d| d.abs() < 1e-6);
+        assert!(close(slerp(&a, &b, 0.0), &a));
+        assert!(close(slerp(&a, &b, 1.0), &b));
+
+        // 直交するベクトルの中間は 45 度の向きになる
+        let mid = slerp(&a, &b, 0.5);
+        assert!((mid[0] - mid[1] * 0.5).abs() < 1e-6, "{}", mid);
+    }
+
+    #[test]
+    fn slerp_near_parallel_falls_back_to_lerp() {
+        let a = Array1::from(vec![1.0, 2.0]);
+        let b = Array1::from(vec![2.0, 4.0]);
+        assert_eq!(slerp(&a, &b, 0.5), lerp(&a, &b, 0.5));
+
+        let zero = Array1::from(vec![0.0, 0.0]);
+        assert_eq!(slerp(&zero, &b, 0.5), lerp(&zero, &b, 0.5));
+    }
+
+    #[test]
+    fn load_style_valid() {
+        let json = r#"{"shape": [2, 2], "data": [[0.0, 1.0], [2.0, 3.0]]}"#;
+        let style_vectors = load_style(json).unwrap();
+        assert_eq!(style_vectors.shape(), &[2, 2]);
+    }
+
+    #[test]
+    fn save_style_round_trip() {
+        let style_vectors = style_vectors();
+        let loaded = load_style(save_style(&style_vectors).unwrap()).unwrap();
+        assert_eq!(loaded, style_vectors);
+    }
+
+    #[test]
+    fn load_style_from_npy() {
+        let mut npy = b"\x93NUMPY\x01\x00".to_vec();
+        let header = "{'descr': '<f4', 'fortran_order': False, 'shape': (2, 2), }";
+        npy.extend((header.len() as u16).to_le_bytes());
+        npy.extend(header.as_bytes());
+        for x in [0.0f32, 1.0, 2.0, 3.0] {
+            npy.extend(x.to_le_bytes());
+        }
+
+        let style_vectors = load_style(npy).unwrap();
+        assert_eq!(style_vectors.row(1).to_vec(), vec![2.0, 3.0]);
+    }
+
+    #[test]
+    fn load_style_from_safetensors() {
+        let header = r#"{"style_vectors":{"dtype":"F32","shape":[2,2],"data_offsets":[0,16]}}"#;
+        let mut safetensors = (header.len() as u64).to_le_bytes().to_vec();
+        safetensors.extend(header.as_bytes());
+        for x in [0.0f32, 1.0, 2.0, 3.0] {
+            safetensors.extend(x.to_le_bytes());
+        }
+
+        let style_vectors = load_style(safetensors).unwrap();
+        assert_eq!(style_vectors.row(1).to_vec(), vec![2.0, 3.0]);
+    }
+
+    #[test]
+    fn load_style_truncated_safetensors() {
+        let safetensors = |header: &str, data_len: usize| {
+            let mut bytes = (header.len() as u64).to_le_bytes().to_vec();
+            bytes.extend(header.as_bytes());
+            bytes.extend(vec![0u8; data_len]);
+            bytes
+        };
+
+        // データが足りない
+        let header = r#"{"style_vectors":{"dtype":"F32","shape":[2,2],"data_offsets":[0,16]}}"#;
+        let err = load_style_safetensors(&safetensors(header, 8)).unwrap_err();
+        assert!(err.to_string().contains("truncated"), "{}", err);
+
+        // オフセットが大きすぎる
+        let header = format!(
+            r#"{{"style_vectors":{{"dtype":"F32","shape":[2,2],"data_offsets":[{},{}]}}}}"#,
+            usize::MAX - 1,
+            usize::MAX
+        );
+        assert!(load_style_safetensors(&safetensors(&header, 16)).is_err());
+
+        // 要素数を掛けるとあふれる形状
+        let header = format!(
+            r#"{{"style_vectors":{{"dtype":"F32","shape":[{},2],"data_offsets":[0,16]}}}}"#,
+            usize::MAX
+        );
+        assert!(load_style_safetensors(&safetensors(&header, 16)).is_err());
+
+        // ヘッダー長がファイルより大きい
+        let mut bytes = u64::MAX.to_le_bytes().to_vec();
+        bytes.extend(b"{}");
+        assert!(load_style_safetensors(&bytes).is_err());
+        assert!(load_style_safetensors(b"{}").is_err());
+    }
+
+    #[test]
+    fn load_style_truncated_npy() {
+        let mut npy = b"\x93NUMPY\x02\x00".to_vec();
+        npy.extend(u32::MAX.to_le_bytes());
+        npy.extend(b"{'descr': '<f4'");
+        assert!(load_style(npy).is_err());
+    }
+
+    #[test]
+    fn load_style_from_npz() {
+        let mut npy = b"\x93NUMPY\x01\x00".to_vec();
+        let header = "{'descr': '<f4', 'fortran_order': False, 'shape': (2, 2), }";
+        npy.extend((header.len() as u16).to_le_bytes());
+        npy.extend(header.as_bytes());
+        for x in [0.0f32, 1.0, 2.0, 3.0] {
+            npy.extend(x.to_le_bytes());
+        }
+
+        let npz = |name: &str| {
+            let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+            writer.start_file(name, options).unwrap();
+            std::io::Write::write_all(&mut writer, &npy).unwrap();
+            writer.finish().unwrap().into_inner()
+        };
+
+        let style_vectors = load_style(npz("arr_0.npy")).unwrap();
+        assert_eq!(style_vectors.row(1).to_vec(), vec![2.0, 3.0]);
+
+        let err = load_style(npz("arr_0.txt")).unwrap_err();
+        assert!(err.to_string().contains("no .npy file"), "{}", err);
+
+        // 途中で切れた zip
+        let truncated = npz("arr_0.npy");
+        assert!(load_style(&truncated[..truncated.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn load_style_shape_mismatch() {
+        let json = r#"{"shape": [3, 2], "data": [[0.0, 1.0], [2.0, 3.0]]}"#;
+        assert!(load_style(json).is_err());
+    }
+
+    #[test]
+    fn load_style_row_length_mismatch() {
+        let json = r#"{"shape": [2, 2], "data": [[0.0], [1.0, 2.0, 3.0]]}"#;
+        assert!(load_style(json).is_err());
+    }
+}
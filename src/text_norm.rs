@@ -0,0 +1,460 @@
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+
+/// Text normalize options
+///
+/// # Fields
+/// - `dates`: Read `2025/10/16`, `2025-10-16` and `10/16` as dates
+/// - `times`: Read `12:30` and `12:30:15` as times
+/// - `currency`: Read `¥1,980`, `$5` etc. with the currency name
+/// - `units`: Read `3.5kg`, `25℃` etc. with the unit name
+/// - `percentages`: Read `50%` as パーセント
+/// - `ordinals`: Read `1st`, `2nd` etc. as 番目
+/// - `ranges`: Read `10〜20` as から
+/// - `phone_numbers`: Read phone numbers digit by digit
+/// - `digit_by_digit_min_len`: Read digit runs of at least this length (IDs etc.) digit by digit
+/// - `fullwidth`: Convert full-width digits and `％` to half-width before the other rules
+/// - `numbers`: Read the remaining numbers as kanji numerals
+#[derive(Debug, Clone)]
+pub struct TextNormalizeOptions {
+    pub dates: bool,
+    pub times: bool,
+    pub currency: bool,
+    pub units: bool,
+    pub percentages: bool,
+    pub ordinals: bool,
+    pub ranges: bool,
+    pub phone_numbers: bool,
+    pub digit_by_digit_min_len: Option<usize>,
+    pub fullwidth: bool,
+    pub numbers: bool,
+}
+
+impl Default for TextNormalizeOptions {
+    fn default() -> Self {
+        TextNormalizeOptions {
+            dates: true,
+            times: true,
+            currency: true,
+            units: true,
+            percentages: true,
+            ordinals: true,
+            ranges: true,
+            phone_numbers: true,
+            digit_by_digit_min_len: None,
+            fullwidth: true,
+            numbers: true,
+        }
+    }
+}
+
+impl TextNormalizeOptions {
+    /// 全ての変換を無効にする (jpreprocess の既定の読みのみを使う)
+    pub fn disabled() -> Self {
+        TextNormalizeOptions {
+            dates: false,
+            times: false,
+            currency: false,
+            units: false,
+            percentages: false,
+            ordinals: false,
+            ranges: false,
+            phone_numbers: false,
+            digit_by_digit_min_len: None,
+            fullwidth: false,
+            numbers: false,
+        }
+    }
+}
+
+// カンマ区切り・小数を含む数値
+const NUM: &str = r"[0-9]+(?:,[0-9]{3})*(?:\.[0-9]+)?";
+
+const KANJI_DIGITS: [&str; 10] = ["〇", "一", "二", "三", "四", "五", "六", "七", "八", "九"];
+const KANA_DIGITS: [&str; 10] = [
+    "ゼロ",
+    "イチ",
+    "ニー",
+    "サン",
+    "ヨン",
+    "ゴー",
+    "ロク",
+    "ナナ",
+    "ハチ",
+    "キュー",
+];
+
+// 長いものから順に並べる (正規表現の選択は先に書いたものが優先されるため)
+const UNITS: [(&str, &str); 40] = [
+    ("km/h", "キロメートル毎時"),
+    ("m/s", "メートル毎秒"),
+    ("kcal", "キロカロリー"),
+    ("kHz", "キロヘルツ"),
+    ("MHz", "メガヘルツ"),
+    ("GHz", "ギガヘルツ"),
+    ("°C", "度"),
+    ("°F", "度"),
+    ("kg", "キログラム"),
+    ("mg", "ミリグラム"),
+    ("km", "キロメートル"),
+    ("cm", "センチメートル"),
+    ("mm", "ミリメートル"),
+    ("m2", "平方メートル"),
+    ("m3", "立方メートル"),
+    ("mL", "ミリリットル"),
+    ("ml", "ミリリットル"),
+    ("dB", "デシベル"),
+    ("KB", "キロバイト"),
+    ("kB", "キロバイト"),
+    ("MB", "メガバイト"),
+    ("GB", "ギガバイト"),
+    ("TB", "テラバイト"),
+    ("Hz", "ヘルツ"),
+    ("kW", "キロワット"),
+    ("ms", "ミリ秒"),
+    ("℃", "度"),
+    ("℉", "度"),
+    ("°", "度"),
+    ("㎏", "キログラム"),
+    ("㎎", "ミリグラム"),
+    ("㎞", "キロメートル"),
+    ("㎝", "センチメートル"),
+    ("㎜", "ミリメートル"),
+    ("㎡", "平方メートル"),
+    ("g", "グラム"),
+    ("m", "メートル"),
+    ("L", "リットル"),
+    ("l", "リットル"),
+    ("W", "ワット"),
+];
+
+static FULLWIDTH_PATTERN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[０-９％]").unwrap());
+static RANGE_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!(r"({NUM})\s*[〜～~]\s*([0-9¥￥$＄€£])")).unwrap());
+static DATE_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"([0-9]+)([/\-.])([0-9]+)([/\-.])([0-9]+)").unwrap());
+static PHONE_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"([0-9]+)-([0-9]+)-([0-9]+)").unwrap());
+static TIME_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"([0-9]+)[:：]([0-9]+)(?:[:：]([0-9]+))?").unwrap());
+static MONTH_DAY_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"([0-9]+)[/／]([0-9]+)").unwrap());
+static CURRENCY_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!(r"([¥￥$＄€£])\s*({NUM})")).unwrap());
+static PERCENT_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!(r"({NUM})\s*%")).unwrap());
+static UNIT_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    let units: Vec<String> = UNITS.iter().map(|(u, _)| regex::escape(u)).collect();
+    Regex::new(&format!(r"({NUM})\s*({})([A-Za-z]?)", units.join("|"))).unwrap()
+});
+static ORDINAL_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"([0-9]+)(st|nd|rd|th)([A-Za-z]?)").unwrap());
+static DIGITS_PATTERN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[0-9]+").unwrap());
+static NUMBER_PATTERN: LazyLock<Regex> = LazyLock::new(|| Regex::new(NUM).unwrap());
+
+/// 数字・日付・時刻・通貨・単位などを読み上げやすい日本語に変換する
+pub fn normalize_numbers(text: &str, options: &TextNormalizeOptions) -> String {
+    let mut text = text.to_string();
+
+    if options.fullwidth {
+        text = FULLWIDTH_PATTERN
+            .replace_all(&text, |caps: &Captures| {
+                let c = caps[0].chars().next().unwrap();
+                char::from_u32(c as u32 - 0xFEE0).unwrap().to_string()
+            })
+            .to_string();
+    }
+
+    if options.ranges {
+        text = RANGE_PATTERN.replace_all(&text, "${1}から${2}").to_string();
+    }
+
+    if options.dates {
+        text = DATE_PATTERN
+            .replace_all(&text, |caps: &Captures| {
+                let (year, month, day) = (&caps[1], &caps[3], &caps[5]);
+
+                if caps[2] != caps[4] || year.len() != 4 || !is_month_day(month, day) {
+                    return caps[0].to_string();
+                }
+
+                format!(
+                    "{}年{}月{}日",
+                    number_to_kanji(year),
+                    number_to_kanji(month),
+                    number_to_kanji(day)
+                )
+            })
+            .to_string();
+    }
+
+    if options.phone_numbers {
+        text = PHONE_PATTERN
+            .replace_all(&text, |caps: &Captures| {
+                let digit_count = caps[0].len() - 2;
+
+                if !caps[1].starts_with('0') || !(10..=11).contains(&digit_count) {
+                    return caps[0].to_string();
+                }
+
+                let groups: Vec<String> = (1..=3).map(|i| digits_to_kana(&caps[i])).collect();
+                groups.join("、")
+            })
+            .to_string();
+    }
+
+    if options.times {
+        text = TIME_PATTERN
+            .replace_all(&text, |caps: &Captures| {
+                let hour: u32 = caps[1].parse().unwrap_or(u32::MAX);
+                let minute: u32 = caps[2].parse().unwrap_or(u32::MAX);
+                let second = caps.get(3).map(|s| s.as_str().parse().unwrap_or(u32::MAX));
+
+                if hour > 30 || caps[2].len() != 2 || minute >= 60 || second.unwrap_or(0) >= 60 {
+                    return caps[0].to_string();
+                }
+
+                let mut result = format!("{}時", number_to_kanji(&caps[1]));
+                if minute != 0 || second.is_some() {
+                    result += &format!("{}分", number_to_kanji(&minute.to_string()));
+                }
+                if let Some(second) = second {
+                    result += &format!("{}秒", number_to_kanji(&second.to_string()));
+                }
+
+                result
+            })
+            .to_string();
+    }
+
+    if options.dates {
+        text = MONTH_DAY_PATTERN
+            .replace_all(&text, |caps: &Captures| {
+                if !is_month_day(&caps[1], &caps[2]) {
+                    return caps[0].to_string();
+                }
+
+                format!(
+                    "{}月{}日",
+                    number_to_kanji(&caps[1]),
+                    number_to_kanji(&caps[2])
+                )
+            })
+            .to_string();
+    }
+
+    if options.currency {
+        text = CURRENCY_PATTERN
+            .replace_all(&text, |caps: &Captures| {
+                let name = match &caps[1] {
+                    "¥" | "￥" => "円",
+                    "$" | "＄" => "ドル",
+                    "€" => "ユーロ",
+                    _ => "ポンド",
+                };
+
+                format!("{}{}", number_to_kanji(&caps[2]), name)
+            })
+            .to_string();
+    }
+
+    if options.percentages {
+        text = PERCENT_PATTERN
+            .replace_all(&text, |caps: &Captures| {
+                format!("{}パーセント", number_to_kanji(&caps[1]))
+            })
+            .to_string();
+    }
+
+    if options.units {
+        text = UNIT_PATTERN
+            .replace_all(&text, |caps: &Captures| {
+                // 後ろに英字が続く場合は単位ではないとみなす
+                if !caps[3].is_empty() {
+                    return caps[0].to_string();
+                }
+
+                let (_, name) = UNITS.iter().find(|(u, _)| *u == &caps[2]).unwrap();
+                format!("{}{}", number_to_kanji(&caps[1]), name)
+            })
+            .to_string();
+    }
+
+    if options.ordinals {
+        text = ORDINAL_PATTERN
+            .replace_all(&text, |caps: &Captures| {
+                if !caps[3].is_empty() {
+                    return caps[0].to_string();
+                }
+
+                format!("{}番目", number_to_kanji(&caps[1]))
+            })
+            .to_string();
+    }
+
+    if let Some(min_len) = options.digit_by_digit_min_len {
+        text = DIGITS_PATTERN
+            .replace_all(&text, |caps: &Captures| {
+                if caps[0].len() < min_len {
+                    return caps[0].to_string();
+                }
+
+                digits_to_kana(&caps[0])
+            })
+            .to_string();
+    }
+
+    // 残りの数値はカンマを取り除いて漢数字にする
+    if options.numbers {
+        text = NUMBER_PATTERN
+            .replace_all(&text, |caps: &Captures| number_to_kanji(&caps[0]))
+            .to_string();
+    }
+
+    text
+}
+
+fn is_month_day(month: &str, day: &str) -> bool {
+    let month: u32 = month.parse().unwrap_or(0);
+    let day: u32 = day.parse().unwrap_or(0);
+
+    (1..=12).contains(&month) && (1..=31).contains(&day)
+}
+
+fn digits_to_kana(digits: &str) -> String {
+    digits
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|d| KANA_DIGITS[d as usize])
+        .collect()
+}
+
+/// `1,980` → 千九百八十, `3.5` → 三点五
+pub fn number_to_kanji(number: &str) -> String {
+    let number = number.replace(',', "");
+    let (integer, decimal) = match number.split_once('.') {
+        Some((integer, decimal)) => (integer, Some(decimal)),
+        None => (number.as_str(), None),
+    };
+
+    let mut result = match integer.parse::<u64>() {
+        Ok(n) => integer_to_kanji(n),
+        // 桁数が多すぎる場合は一桁ずつ読む
+        Err(_) => digits_to_kana(integer),
+    };
+
+    if let Some(decimal) = decimal {
+        result += "点";
+        for d in decimal.chars().filter_map(|c| c.to_digit(10)) {
+            result += KANJI_DIGITS[d as usize];
+        }
+    }
+
+    result
+}
+
+fn integer_to_kanji(mut n: u64) -> String {
+    if n == 0 {
+        return "零".to_string();
+    }
+
+    const LARGE_UNITS: [&str; 5] = ["", "万", "億", "兆", "京"];
+    const SMALL_UNITS: [&str; 4] = ["", "十", "百", "千"];
+
+    let mut groups = vec![];
+    while n > 0 {
+        groups.push(n % 10000);
+        n /= 10000;
+    }
+
+    let mut result = String::new();
+    for (i, group) in groups.iter().enumerate().rev() {
+        if *group == 0 {
+            continue;
+        }
+
+        let mut group_text = String::new();
+        for j in (0..4).rev() {
+            let digit = (group / 10u64.pow(j as u32) % 10) as usize;
+            match digit {
+                0 => continue,
+                // 十・百・千の前の一は読まない
+                1 if j > 0 => (),
+                _ => group_text += KANJI_DIGITS[digit],
+            }
+            group_text += SMALL_UNITS[j];
+        }
+
+        result += &group_text;
+        result += LARGE_UNITS[i];
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(text: &str) -> String {
+        normalize_numbers(text, &TextNormalizeOptions::default())
+    }
+
+    #[test]
+    fn normalize_times() {
+        assert_eq!(normalize("12:30"), "十二時三十分");
+        assert_eq!(normalize("9:00"), "九時");
+        assert_eq!(normalize("12:30:15"), "十二時三十分十五秒");
+    }
+
+    #[test]
+    fn normalize_dates() {
+        assert_eq!(normalize("2025/10/16"), "二千二十五年十月十六日");
+        assert_eq!(normalize("2025-10-16"), "二千二十五年十月十六日");
+        assert_eq!(normalize("10/16"), "十月十六日");
+    }
+
+    #[test]
+    fn normalize_currency_units_and_percentages() {
+        assert_eq!(normalize("¥1,980"), "千九百八十円");
+        assert_eq!(normalize("3.5kg"), "三点五キログラム");
+        assert_eq!(normalize("25℃"), "二十五度");
+        assert_eq!(normalize("50%"), "五十パーセント");
+        assert_eq!(normalize("５０％"), "五十パーセント");
+    }
+
+    #[test]
+    fn normalize_phone_numbers() {
+        assert_eq!(
+            normalize("090-1234-5678"),
+            "ゼロキューゼロ、イチニーサンヨン、ゴーロクナナハチ"
+        );
+        // 0 から始まらないものは電話番号として扱わない
+        assert_eq!(normalize("1-2-3"), "一-二-三");
+    }
+
+    #[test]
+    fn normalize_remaining_numbers() {
+        assert_eq!(normalize("0"), "零");
+        assert_eq!(normalize("10000"), "一万");
+        assert_eq!(normalize("1,000,000"), "百万");
+    }
+
+    #[test]
+    fn disabled_leaves_text_untouched() {
+        let options = TextNormalizeOptions::disabled();
+
+        for text in [
+            "12:30",
+            "2025/10/16",
+            "¥1,980",
+            "3.5kg",
+            "25℃",
+            "50%",
+            "５０％",
+        ] {
+            assert_eq!(normalize_numbers(text, &options), text);
+        }
+    }
+}
@@ -19,3 +19,74 @@ pub fn tokenize(text: &str, tokenizer: &Tokenizer) -> Result<(Vec<i64>, Vec<i64>
 
     Ok((token_ids, attention_masks))
 }
+
+/// 英語などの空白区切りの言語用
+///
+/// 特殊トークンを含む token_ids, attention_masks と、特殊トークンを除いたトークンを単語ごとにまとめたものを返す
+pub fn tokenize_words(
+    text: &str,
+    tokenizer: &Tokenizer,
+) -> Result<(Vec<i64>, Vec<i64>, Vec<Vec<String>>), Sbv2CoreError> {
+    let encoding = tokenizer.encode(text, true)?;
+
+    let token_ids: Vec<i64> = encoding.get_ids().iter().map(|&x| x as i64).collect();
+    let attention_masks: Vec<i64> = encoding
+        .get_attention_mask()
+        .iter()
+        .map(|&x| x as i64)
+        .collect();
+
+    let tokens: Vec<&str> = encoding
+        .get_tokens()
+        .iter()
+        .zip(encoding.get_special_tokens_mask())
+        .filter(|(_, special)| **special == 0)
+        .map(|(token, _)| token.as_str())
+        .collect();
+
+    Ok((token_ids, attention_masks, group_words(&tokens)))
+}
+
+// SentencePiece のトークンを単語ごとにまとめる
+pub(crate) fn group_words(tokens: &[&str]) -> Vec<Vec<String>> {
+    let mut words: Vec<Vec<String>> = vec![];
+    for (i, token) in tokens.iter().enumerate() {
+        // SentencePiece の単語の先頭を表す記号
+        if let Some(stripped) = token.strip_prefix('\u{2581}') {
+            words.push(vec![stripped.to_string()]);
+            continue;
+        }
+
+        let is_punctuation = |t: &str| crate::norm::PUNCTUATIONS.contains(&t);
+
+        // 句読点の直後に単語の続きがある場合 (don't など) は同じ単語にまとめる
+        let joins_next = tokens
+            .get(i + 1)
+            .is_some_and(|next| !next.starts_with('\u{2581}') && !is_punctuation(next));
+
+        if words.is_empty() || (is_punctuation(token) && !joins_next) {
+            words.push(vec![token.to_string()]);
+        } else {
+            words.last_mut().unwrap().push(token.to_string());
+        }
+    }
+
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_words_by_word_start() {
+        let words = group_words(&["\u{2581}hel", "lo", "\u{2581}world", "."]);
+        assert_eq!(words, [vec!["hel", "lo"], vec!["world"], vec!["."]]);
+    }
+
+    #[test]
+    fn group_words_keeps_contractions() {
+        let words = group_words(&["\u{2581}don", "'", "t", "\u{2581}go", "!"]);
+        assert_eq!(words, [vec!["don", "'", "t"], vec!["go"], vec!["!"]]);
+    }
+}
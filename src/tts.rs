@@ -1,29 +1,63 @@
-use std::{
-    io::{Cursor, Read as _},
-    path::PathBuf,
-};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
-use ndarray::{Array1, Array2, Array3, Axis};
+use ndarray::{Array1, Array2, Array3, ArrayView1, Axis};
 use ort::Session;
 use tokenizers::Tokenizer;
 
-use crate::{errors::Sbv2CoreError, jtalk::JTalk};
+use crate::{
+    bert::BertBatcher,
+    chinese::ChineseG2p,
+    config::ModelConfig,
+    english::EnglishG2p,
+    english_kana::EnglishKana,
+    errors::Sbv2CoreError,
+    execution_provider::ExecutionProviderKind,
+    jtalk::JTalk,
+    model::{ModelVariant, ParallelSynthesisConfig, SessionConfig, SynthesisParams},
+    model_storage::{ModelOrigin, ModelSource, ModelStorage},
+    nlp::Language,
+    style::StyleSpec,
+    text_norm::TextNormalizeOptions,
+    tts_util::{ParseOptions, ParsedText},
+};
+
+// 同じ VITS2 のモデルを同じ設定で読み込んだモデルの間で共有する session
+#[derive(Debug)]
+struct Vits2Sessions {
+    vits2: Session,
+    // 並列で合成する場合の追加の session
+    vits2_pool: Vec<Session>,
+    // 読み込んだときの並列合成の設定
+    parallel_synthesis: Option<ParallelSynthesisConfig>,
+    execution_provider: ExecutionProviderKind,
+}
 
 #[derive(Debug)]
 struct NoUpperLimitTtsModel {
     model_ident: String,
 
-    vits2: Session,
+    sessions: Arc<Vits2Sessions>,
+    // VITS2 のモデルの内容のハッシュ
+    model_hash: [u8; 32],
+    variant: ModelVariant,
+    session_config: SessionConfig,
     style_vectors: Array2<f32>,
+    config: ModelConfig,
 }
 
 #[derive(Debug)]
 struct UpperLimitTtsModel {
     model_ident: String,
 
-    vits2: Option<Session>,
-    bytes: Vec<u8>,
+    // session が読み込まれていない場合は None
+    sessions: Option<Arc<Vits2Sessions>>,
+    model_hash: [u8; 32],
+    variant: ModelVariant,
+    session_config: SessionConfig,
+    // session を読み込み直すためのモデル
+    source: Arc<ModelSource>,
     style_vectors: Array2<f32>,
+    config: ModelConfig,
 }
 
 #[derive(Debug)]
@@ -44,7 +78,22 @@ pub struct TtsModelHolder {
 
     bert: Session,
     tokenizer: Tokenizer,
+    // 同時に合成する要求の BERT の推論をまとめる
+    bert_batcher: BertBatcher,
     jtalk: JTalk,
+    english_kana: EnglishKana,
+
+    // 日本語以外の BERT と tokenizer
+    lang_berts: HashMap<Language, (Session, Tokenizer)>,
+    english_g2p: EnglishG2p,
+    chinese_g2p: ChineseG2p,
+
+    parallel_synthesis: Option<ParallelSynthesisConfig>,
+    model_storage: ModelStorage,
+    bert_session_config: SessionConfig,
+    vits2_session_config: SessionConfig,
+    // 各言語の BERT の session に登録された provider
+    bert_execution_providers: HashMap<Language, ExecutionProviderKind>,
 }
 
 impl TtsModelHolder {
@@ -56,7 +105,31 @@ impl TtsModelHolder {
     where
         T: AsRef<[u8]>,
     {
-        let bert = crate::model::load_model_session(bert_model_bytes, true)?;
+        Self::new_with_session_config(
+            bert_model_bytes,
+            tokenizer_bytes,
+            max_loaded_models,
+            SessionConfig::default(),
+            SessionConfig::default(),
+        )
+    }
+
+    /// `new` と同じだが、BERT と VITS2 の session の設定を指定する
+    ///
+    /// `vits2_session_config` は `load_with_session_config` などで指定しなかったモデルに使われる
+    pub fn new_with_session_config<T>(
+        bert_model_bytes: T,
+        tokenizer_bytes: T,
+        max_loaded_models: Option<usize>,
+        bert_session_config: SessionConfig,
+        vits2_session_config: SessionConfig,
+    ) -> Result<Self, Sbv2CoreError>
+    where
+        T: AsRef<[u8]>,
+    {
+        let (bert, bert_provider) =
+            crate::model::load_model_session(bert_model_bytes, None, true, &bert_session_config)?;
+        crate::model::validate_bert(&bert)?;
         let tokenizer = Tokenizer::from_bytes(tokenizer_bytes)?;
 
         let models = match max_loaded_models {
@@ -67,7 +140,17 @@ impl TtsModelHolder {
         Ok(TtsModelHolder {
             bert,
             tokenizer,
+            bert_batcher: BertBatcher::default(),
             jtalk: JTalk::new()?,
+            english_kana: EnglishKana::new(),
+            lang_berts: HashMap::new(),
+            english_g2p: EnglishG2p::new(),
+            chinese_g2p: ChineseG2p::new(),
+            parallel_synthesis: None,
+            model_storage: ModelStorage::default(),
+            bert_session_config,
+            vits2_session_config,
+            bert_execution_providers: HashMap::from([(Language::Jp, bert_provider)]),
             models,
             max_loaded_models,
         })
@@ -78,6 +161,26 @@ impl TtsModelHolder {
         tokenizer: P,
         max_loaded_models: Option<usize>,
     ) -> Result<Self, Sbv2CoreError>
+    where
+        P: Into<PathBuf>,
+    {
+        Self::new_from_filepath_with_session_config(
+            bert_model,
+            tokenizer,
+            max_loaded_models,
+            SessionConfig::default(),
+            SessionConfig::default(),
+        )
+    }
+
+    /// `new_from_filepath` と同じだが、BERT と VITS2 の session の設定を指定する
+    pub fn new_from_filepath_with_session_config<P>(
+        bert_model: P,
+        tokenizer: P,
+        max_loaded_models: Option<usize>,
+        bert_session_config: SessionConfig,
+        vits2_session_config: SessionConfig,
+    ) -> Result<Self, Sbv2CoreError>
     where
         P: Into<PathBuf>,
     {
@@ -87,51 +190,182 @@ impl TtsModelHolder {
         let bert_model_bytes = std::fs::read(bert_model)?;
         let tokenizer_bytes = std::fs::read(tokenizer)?;
 
-        Self::new(bert_model_bytes, tokenizer_bytes, max_loaded_models)
+        Self::new_with_session_config(
+            bert_model_bytes,
+            tokenizer_bytes,
+            max_loaded_models,
+            bert_session_config,
+            vits2_session_config,
+        )
+    }
+
+    /// 指定した言語の BERT と tokenizer を読み込む (日本語の場合は `new` で読み込んだものを置き換える)
+    pub fn load_bert<T>(
+        &mut self,
+        language: Language,
+        bert_model_bytes: T,
+        tokenizer_bytes: T,
+    ) -> Result<(), Sbv2CoreError>
+    where
+        T: AsRef<[u8]>,
+    {
+        let (bert, bert_provider) = crate::model::load_model_session(
+            bert_model_bytes,
+            None,
+            true,
+            &self.bert_session_config,
+        )?;
+        crate::model::validate_bert(&bert)?;
+        let tokenizer = Tokenizer::from_bytes(tokenizer_bytes)?;
+        self.bert_execution_providers
+            .insert(language, bert_provider);
+
+        match language {
+            Language::Jp => {
+                self.bert = bert;
+                self.tokenizer = tokenizer;
+            }
+            _ => {
+                self.lang_berts.insert(language, (bert, tokenizer));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// CMUdict 形式の英語の発音辞書を読み込む (同梱の辞書より優先される)
+    ///
+    /// 同梱の辞書は頻出語のみの仮のものなので、英語を合成する場合は CMUdict を読み込む
+    pub fn load_cmudict<T>(&mut self, cmudict_bytes: T) -> Result<(), Sbv2CoreError>
+    where
+        T: AsRef<[u8]>,
+    {
+        self.english_g2p
+            .load_cmudict(&String::from_utf8_lossy(cmudict_bytes.as_ref()))
+    }
+
+    /// `字 zi4`, `银行 yin2 hang2` の形式の拼音辞書を読み込む (同梱の辞書より優先される)
+    pub fn load_pinyin_dict<T>(&mut self, pinyin_dict_bytes: T) -> Result<(), Sbv2CoreError>
+    where
+        T: AsRef<[u8]>,
+    {
+        self.chinese_g2p
+            .load_pinyin_dict(&String::from_utf8_lossy(pinyin_dict_bytes.as_ref()))
+    }
+
+    /// 分割した文を並列で合成する設定 (`None` の場合は並列にしない)
+    ///
+    /// この後に読み込むモデル (上限がある場合は次に session を読み込むモデル) から適用される
+    pub fn set_parallel_synthesis(&mut self, parallel_synthesis: Option<ParallelSynthesisConfig>) {
+        self.parallel_synthesis = parallel_synthesis;
     }
 
+    pub(crate) fn vits2_session_config(&self) -> SessionConfig {
+        self.vits2_session_config.clone()
+    }
+
+    /// `max_loaded_models` が設定されている場合に、session を読み込み直すためのモデルの保持方法
+    ///
+    /// この後に読み込むモデルから適用される
+    pub fn set_model_storage(&mut self, model_storage: ModelStorage) {
+        self.model_storage = model_storage;
+    }
+
+    /// 読み込まれている VITS2 の session の数 (複数の識別子で共有している session は一つと数える)
     pub fn get_loadedmodel_count(&self) -> usize {
-        let models = match &self.models {
-            EitherTtsModelVec::Limit(vec) => vec,
-            EitherTtsModelVec::NoLimit(vec) => return vec.len(),
+        let sessions: Vec<&Arc<Vits2Sessions>> = match &self.models {
+            EitherTtsModelVec::Limit(vec) => {
+                vec.iter().filter_map(|m| m.sessions.as_ref()).collect()
+            }
+            EitherTtsModelVec::NoLimit(vec) => vec.iter().map(|m| &m.sessions).collect(),
         };
 
-        let mut loaded_model_count = 0;
-        for i in models {
-            if i.vits2.is_some() {
-                loaded_model_count += 1;
+        let mut distinct: Vec<&Arc<Vits2Sessions>> = vec![];
+        for sessions in sessions {
+            if !distinct.iter().any(|s| Arc::ptr_eq(s, sessions)) {
+                distinct.push(sessions);
             }
         }
 
-        loaded_model_count
+        distinct.len()
     }
 
     pub fn is_max_models_loaded(&self) -> bool {
-        let models = match &self.models {
-            EitherTtsModelVec::Limit(vec) => vec,
-            EitherTtsModelVec::NoLimit(_) => return false,
-        };
+        if matches!(self.models, EitherTtsModelVec::NoLimit(_)) {
+            return false;
+        }
 
         let Some(upper_limit) = self.max_loaded_models else {
             return false;
         };
 
-        let mut loaded_model_count = 0;
-        for i in models {
-            if i.vits2.is_some() {
-                loaded_model_count += 1;
-            }
+        self.get_loadedmodel_count() >= upper_limit
+    }
+
+    /// 読み込んだモデルの VITS2 の session に登録された execution provider
+    ///
+    /// モデルが見つからない場合や、上限により session が読み込まれていない場合は `None`
+    pub fn execution_provider(&self, model_ident: &str) -> Option<ExecutionProviderKind> {
+        match self.get_either_model(model_ident)? {
+            EitherTtsModel::Limit(model) => model.sessions.as_ref().map(|s| s.execution_provider),
+            EitherTtsModel::NoLimit(model) => Some(model.sessions.execution_provider),
         }
+    }
 
-        loaded_model_count >= upper_limit
+    /// 指定した言語の BERT の session に登録された execution provider
+    pub fn bert_execution_provider(&self, language: Language) -> Option<ExecutionProviderKind> {
+        self.bert_execution_providers.get(&language).copied()
     }
 
+    /// VITS2 のモデルとスタイルベクトルを読み込む
+    ///
+    /// 同じ VITS2 のモデルを同じ設定で読み込んでいる場合は session を共有し、
+    /// 共有しているすべての識別子が `unload` されたときに解放する (スタイルベクトルと config は識別子ごと)
     pub fn load<T>(
         &mut self,
         model_ident: &str,
         style_vectors_bytes: T,
         vits2_bytes: Vec<u8>,
     ) -> Result<(), Sbv2CoreError>
+    where
+        T: AsRef<[u8]>,
+    {
+        self.load_with_session_config(
+            model_ident,
+            style_vectors_bytes,
+            vits2_bytes,
+            self.vits2_session_config.clone(),
+        )
+    }
+
+    /// `load` と同じだが、このモデルの VITS2 の session の設定を指定する
+    pub fn load_with_session_config<T>(
+        &mut self,
+        model_ident: &str,
+        style_vectors_bytes: T,
+        vits2_bytes: Vec<u8>,
+        session_config: SessionConfig,
+    ) -> Result<(), Sbv2CoreError>
+    where
+        T: AsRef<[u8]>,
+    {
+        self.load_with_origin(
+            model_ident,
+            style_vectors_bytes,
+            vits2_bytes,
+            ModelOrigin::Bytes,
+            session_config,
+        )
+    }
+
+    pub(crate) fn load_with_origin<T>(
+        &mut self,
+        model_ident: &str,
+        style_vectors_bytes: T,
+        vits2_bytes: Vec<u8>,
+        origin: ModelOrigin,
+        session_config: SessionConfig,
+    ) -> Result<(), Sbv2CoreError>
     where
         T: AsRef<[u8]>,
     {
@@ -140,21 +374,56 @@ impl TtsModelHolder {
         };
 
         let max_loaded = self.is_max_models_loaded();
+        let is_limit = matches!(self.models, EitherTtsModelVec::Limit(_));
+
+        // 同じモデルを同じ設定で読み込んでいれば session と保持しているモデルを共有する
+        let model_hash = crate::model_cache::model_hash(&vits2_bytes);
+        let (shared_sessions, shared_source) = self.find_shared(&model_hash, &session_config);
+
+        // 上限に達している場合は session を読み込まず、最適化しない session でモデルの種類の判定と検証だけを行う
+        let sessions = match shared_sessions {
+            Some(sessions) => Some(sessions),
+            None if is_limit && max_loaded => None,
+            None => Some(Arc::new(self.load_vits2_sessions(
+                &vits2_bytes,
+                &model_hash,
+                &session_config,
+            )?)),
+        };
+        let inspection;
+        let vits2 = match &sessions {
+            Some(sessions) => &sessions.vits2,
+            None => {
+                inspection = crate::model::inspect_model_session(&vits2_bytes)?;
+                &inspection
+            }
+        };
+        let variant = ModelVariant::detect(vits2);
 
+        let model_storage = self.model_storage;
         match &mut self.models {
             EitherTtsModelVec::Limit(vec) => {
                 let style_vectors = crate::style::load_style(style_vectors_bytes)?;
-                let session = if max_loaded {
-                    None
-                } else {
-                    Some(crate::model::load_model_session(&vits2_bytes, false)?)
+                crate::model::validate_vits2(vits2, variant, &style_vectors)?;
+                let source = match shared_source {
+                    Some(source) => source,
+                    None => Arc::new(ModelSource::new(
+                        model_storage,
+                        vits2_bytes,
+                        origin,
+                        model_hash,
+                    )?),
                 };
 
                 let model = UpperLimitTtsModel {
                     model_ident: model_ident.to_string(),
-                    vits2: session,
-                    bytes: vits2_bytes,
+                    sessions,
+                    model_hash,
+                    variant,
+                    session_config,
+                    source,
                     style_vectors,
+                    config: ModelConfig::default(),
                 };
 
                 vec.push(model);
@@ -162,12 +431,19 @@ impl TtsModelHolder {
 
             EitherTtsModelVec::NoLimit(vec) => {
                 let style_vectors = crate::style::load_style(style_vectors_bytes)?;
-                let session = crate::model::load_model_session(&vits2_bytes, false)?;
+                crate::model::validate_vits2(vits2, variant, &style_vectors)?;
+                let Some(sessions) = sessions else {
+                    unreachable!("上限がない場合は常に読み込む")
+                };
 
                 let model = NoUpperLimitTtsModel {
                     model_ident: model_ident.to_string(),
-                    vits2: session,
+                    sessions,
+                    model_hash,
+                    variant,
+                    session_config,
                     style_vectors,
+                    config: ModelConfig::default(),
                 };
                 vec.push(model);
             }
@@ -214,40 +490,196 @@ impl TtsModelHolder {
     where
         T: AsRef<[u8]>,
     {
-        // .sbv2 ファイルから vits2 ファイルと style_vectors.json ファイルを取得
-        let (vits2_bytes, style_vectors_bytes) = {
-            let decoded = zstd::decode_all(Cursor::new(sbv2file_bytes.as_ref()))?;
-            let mut archive = tar::Archive::new(Cursor::new(decoded));
-            let mut entries = archive.entries()?;
-
-            let mut vits2 = None;
-            let mut style_vectors = None;
-
-            while let Some(Ok(mut e)) = entries.next() {
-                let mut file_bytes = Vec::with_capacity(e.size() as usize);
-                e.read_to_end(&mut file_bytes)?;
-
-                let file_name = String::from_utf8_lossy(&e.path_bytes()).to_string();
-                match file_name.as_str() {
-                    "model.onnx" => vits2 = Some(file_bytes),
-                    "style_vectors.json" => style_vectors = Some(file_bytes),
-                    _ => continue,
-                }
-            }
+        self.load_from_sbv2file_with_session_config(
+            model_ident,
+            sbv2file_bytes,
+            self.vits2_session_config.clone(),
+        )
+    }
 
-            let create_err = |content: &str| {
-                return Err(Sbv2CoreError::ModelNotFoundError(content.to_string()));
-            };
+    /// `load_from_sbv2file` と同じだが、このモデルの VITS2 の session の設定を指定する
+    pub fn load_from_sbv2file_with_session_config<T>(
+        &mut self,
+        model_ident: &str,
+        sbv2file_bytes: T,
+        session_config: SessionConfig,
+    ) -> Result<(), Sbv2CoreError>
+    where
+        T: AsRef<[u8]>,
+    {
+        self.load_sbv2_with_origin(
+            model_ident,
+            sbv2file_bytes.as_ref(),
+            ModelOrigin::Sbv2Bytes(sbv2file_bytes.as_ref()),
+            session_config,
+        )
+    }
+
+    pub(crate) fn load_sbv2_with_origin(
+        &mut self,
+        model_ident: &str,
+        sbv2file_bytes: &[u8],
+        origin: ModelOrigin,
+        session_config: SessionConfig,
+    ) -> Result<(), Sbv2CoreError> {
+        // .sbv2 ファイルから vits2 ファイルと style_vectors.json ファイル (と config.json) を取得
+        let (vits2_bytes, style_vectors_bytes, config_bytes) =
+            crate::model_storage::extract_sbv2(sbv2file_bytes)?;
+
+        // config.json が壊れている場合にモデルだけが登録されないよう、先に読み込む
+        let config = config_bytes.map(ModelConfig::from_bytes).transpose()?;
+
+        self.load_with_origin(
+            model_ident,
+            style_vectors_bytes,
+            vits2_bytes,
+            origin,
+            session_config,
+        )?;
+
+        if let Some(config) = config {
+            self.apply_config(model_ident, config)?;
+        }
 
-            match (vits2, style_vectors) {
-                (Some(vits2), Some(style_vectors)) => (vits2, style_vectors),
-                (None, Some(_)) => create_err("vits2 not found")?,
-                (Some(_), None) => create_err("style_vectors not found")?,
-                (None, None) => create_err("vits2, style_vectors not found")?,
+        Ok(())
+    }
+
+    /// 読み込んだモデルの Style-Bert-VITS2 の config.json を読み込み、スタイル名と話者名を使えるようにする
+    pub fn load_config<T>(
+        &mut self,
+        model_ident: &str,
+        config_bytes: T,
+    ) -> Result<(), Sbv2CoreError>
+    where
+        T: AsRef<[u8]>,
+    {
+        let config = ModelConfig::from_bytes(config_bytes)?;
+        self.apply_config(model_ident, config)
+    }
+
+    fn apply_config(
+        &mut self,
+        model_ident: &str,
+        mut config: ModelConfig,
+    ) -> Result<(), Sbv2CoreError> {
+        let (_, model_config) = self.get_model_mut(model_ident)?;
+
+        // 登録済みのカスタムスタイルは引き継ぐ
+        for style_name in &model_config.custom_styles {
+            if let Some(style_id) = model_config.style2id.get(style_name) {
+                config.style2id.insert(style_name.clone(), *style_id);
             }
+        }
+        config.custom_styles = std::mem::take(&mut model_config.custom_styles);
+
+        *model_config = config;
+        Ok(())
+    }
+
+    /// 読み込んだモデルに名前付きのスタイルベクトルを追加し、そのスタイル ID を返す
+    pub fn register_style(
+        &mut self,
+        model_ident: &str,
+        style_name: &str,
+        style_vector: Vec<f32>,
+    ) -> Result<i32, Sbv2CoreError> {
+        let (style_vectors, config) = self.get_model_mut(model_ident)?;
+
+        if config.style2id.contains_key(style_name) {
+            return Err(Sbv2CoreError::ValueError(format!(
+                "style `{}` already exists",
+                style_name
+            )));
+        }
+        if style_vector.len() != style_vectors.ncols() {
+            return Err(Sbv2CoreError::ValueError(format!(
+                "style vector has width {}, expected {}",
+                style_vector.len(),
+                style_vectors.ncols()
+            )));
+        }
+
+        style_vectors.push_row(ArrayView1::from(&style_vector))?;
+        let style_id = (style_vectors.nrows() - 1) as i32;
+
+        config.style2id.insert(style_name.to_string(), style_id);
+        config.custom_styles.push(style_name.to_string());
+
+        Ok(style_id)
+    }
+
+    /// `StyleSpec` で求めたスタイルベクトルを名前付きで追加し、そのスタイル ID を返す
+    pub fn register_style_mix(
+        &mut self,
+        model_ident: &str,
+        style_name: &str,
+        style: &StyleSpec,
+    ) -> Result<i32, Sbv2CoreError> {
+        let style_vector = self.resolve_style(self.get_style_vectors(model_ident)?, style)?;
+        self.register_style(model_ident, style_name, style_vector.to_vec())
+    }
+
+    /// 追加したカスタムスタイルの名前とスタイル ID の一覧
+    pub fn custom_styles(&self, model_ident: &str) -> Result<Vec<(String, i32)>, Sbv2CoreError> {
+        let config = self.get_model_config(model_ident)?;
+
+        Ok(config
+            .styles()
+            .into_iter()
+            .filter(|(name, _)| config.custom_styles.contains(name))
+            .collect())
+    }
+
+    /// カスタムスタイルを削除する (後に追加したカスタムスタイルの ID は 1 つずつ前にずれる)
+    pub fn delete_style(
+        &mut self,
+        model_ident: &str,
+        style_name: &str,
+    ) -> Result<bool, Sbv2CoreError> {
+        let (style_vectors, config) = self.get_model_mut(model_ident)?;
+
+        let Some(idx) = config.custom_styles.iter().position(|n| n == style_name) else {
+            return Ok(false);
         };
+        let Some(style_id) = config.style2id.remove(style_name) else {
+            return Ok(false);
+        };
+        config.custom_styles.remove(idx);
+
+        let rows: Vec<usize> = (0..style_vectors.nrows())
+            .filter(|row| *row != style_id as usize)
+            .collect();
+        *style_vectors = style_vectors.select(Axis(0), &rows);
 
-        self.load(model_ident, style_vectors_bytes, vits2_bytes)
+        for id in config.style2id.values_mut() {
+            if *id > style_id {
+                *id -= 1;
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// カスタムスタイルを含むスタイルベクトルを `style_vectors.json` の形式で返す
+    pub fn export_style_vectors(&self, model_ident: &str) -> Result<Vec<u8>, Sbv2CoreError> {
+        crate::style::save_style(self.get_style_vectors(model_ident)?)
+    }
+
+    /// カスタムスタイルを含むスタイル名と話者名を `load_config` で読み込める config.json の形式で返す
+    ///
+    /// `export_style_vectors` と一緒に保存すると、読み込み直してもスタイル名で指定できる
+    pub fn export_config(&self, model_ident: &str) -> Result<Vec<u8>, Sbv2CoreError> {
+        self.get_model_config(model_ident)?.to_bytes()
+    }
+
+    /// モデルのスタイル名とスタイル ID の一覧 (config.json を読み込んでいない場合は空)
+    pub fn styles(&self, model_ident: &str) -> Result<Vec<(String, i32)>, Sbv2CoreError> {
+        Ok(self.get_model_config(model_ident)?.styles())
+    }
+
+    /// モデルの話者名と話者 ID の一覧 (config.json を読み込んでいない場合は空)
+    pub fn speakers(&self, model_ident: &str) -> Result<Vec<(String, i64)>, Sbv2CoreError> {
+        Ok(self.get_model_config(model_ident)?.speakers())
     }
 
     pub fn model_idents(&self) -> Vec<String> {
@@ -261,6 +693,16 @@ impl TtsModelHolder {
         }
     }
 
+    /// 英単語の読みを登録する (`word` は大文字小文字を区別しない、`kana` はカタカナのみ)
+    pub fn add_english_word(&mut self, word: &str, kana: &str) -> Result<(), Sbv2CoreError> {
+        self.english_kana.add_word(word, kana)
+    }
+
+    /// 登録した英単語の読みを削除する
+    pub fn remove_english_word(&mut self, word: &str) -> bool {
+        self.english_kana.remove_word(word)
+    }
+
     // 上限が設定されている場合モデルのsessionをNoneにする
     fn session_unload(&mut self, model_ident: &str) -> Result<(), Sbv2CoreError> {
         let models = match &mut self.models {
@@ -268,11 +710,24 @@ impl TtsModelHolder {
             EitherTtsModelVec::NoLimit(_) => return Ok(()),
         };
 
-        let Some(model) = models.iter_mut().find(|i| i.model_ident == model_ident) else {
+        let Some(sessions) = models
+            .iter()
+            .find(|i| i.model_ident == model_ident)
+            .and_then(|m| m.sessions.clone())
+        else {
             return Ok(());
         };
 
-        model.vits2 = None;
+        // 同じ session を共有しているモデルもまとめて取り除かないと解放されない
+        for model in models.iter_mut() {
+            if model
+                .sessions
+                .as_ref()
+                .is_some_and(|s| Arc::ptr_eq(s, &sessions))
+            {
+                model.sessions = None;
+            }
+        }
 
         Ok(())
     }
@@ -291,15 +746,19 @@ impl TtsModelHolder {
                 .find(|(_, i)| i.model_ident == model_ident)
                 .ok_or(Sbv2CoreError::ModelNotFoundError(model_ident.to_string()))?;
 
-            if model.vits2.is_some() {
+            if model.sessions.is_some() {
                 return Ok(());
             }
 
             models.remove(idx)
         };
 
+        // 同じモデルの session が読み込まれていれば、取り除かれる前に共有しておく
+        let (shared_sessions, _) = self.find_shared(&model.model_hash, &model.session_config);
+
         // すでに max - 1 (上で取り除いた分を除く) 個のモデルのSessionがsomeなら、一番古いものを取り除く
-        if let Some(max_loaded_models) = self.max_loaded_models {
+        // (共有できる session がある場合は新しく読み込まないので取り除かない)
+        if let (Some(max_loaded_models), None) = (self.max_loaded_models, &shared_sessions) {
             let loaded_model_count = self.get_loadedmodel_count();
 
             let remove_model_ident = {
@@ -308,7 +767,7 @@ impl TtsModelHolder {
                     EitherTtsModelVec::NoLimit(_) => return Ok(()),
                 };
 
-                if let Some(remove_model) = models.iter().find(|m| m.vits2.is_some()) {
+                if let Some(remove_model) = models.iter().find(|m| m.sessions.is_some()) {
                     remove_model.model_ident.clone()
                 } else {
                     "".to_string()
@@ -320,7 +779,17 @@ impl TtsModelHolder {
             }
         };
 
-        let sbv2_session = crate::model::load_model_session(&model.bytes, false)?;
+        let sessions = match shared_sessions {
+            Some(sessions) => sessions,
+            None => {
+                let vits2_bytes = model.source.read()?;
+                Arc::new(self.load_vits2_sessions(
+                    &vits2_bytes,
+                    &model.model_hash,
+                    &model.session_config,
+                )?)
+            }
+        };
 
         let models = match &mut self.models {
             EitherTtsModelVec::Limit(vec) => vec,
@@ -329,14 +798,95 @@ impl TtsModelHolder {
 
         models.push(UpperLimitTtsModel {
             model_ident: model_ident.to_string(),
-            vits2: Some(sbv2_session),
-            bytes: model.bytes,
+            sessions: Some(sessions),
+            model_hash: model.model_hash,
+            variant: model.variant,
+            session_config: model.session_config,
+            source: model.source,
             style_vectors: model.style_vectors,
+            config: model.config,
         });
 
         Ok(())
     }
 
+    // 並列合成が設定されている場合は、スレッド数を減らした session と追加の session を読み込む
+    fn load_vits2_sessions(
+        &self,
+        vits2_bytes: &[u8],
+        model_hash: &[u8; 32],
+        session_config: &SessionConfig,
+    ) -> Result<Vits2Sessions, Sbv2CoreError> {
+        let Some(parallel) = self.parallel_synthesis else {
+            let (vits2, execution_provider) = crate::model::load_model_session(
+                vits2_bytes,
+                Some(model_hash),
+                false,
+                session_config,
+            )?;
+            return Ok(Vits2Sessions {
+                vits2,
+                vits2_pool: vec![],
+                parallel_synthesis: None,
+                execution_provider,
+            });
+        };
+
+        let session_config = parallel.session_config(session_config);
+        let load = || {
+            crate::model::load_model_session(vits2_bytes, Some(model_hash), false, &session_config)
+        };
+
+        let (vits2, execution_provider) = load()?;
+        let mut vits2_pool = vec![];
+        for _ in 1..parallel.sentence_workers {
+            vits2_pool.push(load()?.0);
+        }
+
+        Ok(Vits2Sessions {
+            vits2,
+            vits2_pool,
+            parallel_synthesis: Some(parallel),
+            execution_provider,
+        })
+    }
+
+    // 同じモデルを同じ設定で読み込んだモデルの (読み込まれている session, 保持しているモデル)
+    // (session は現在の並列合成の設定で読み込んだもののみ共有する)
+    fn find_shared(
+        &self,
+        model_hash: &[u8; 32],
+        session_config: &SessionConfig,
+    ) -> (Option<Arc<Vits2Sessions>>, Option<Arc<ModelSource>>) {
+        let same_parallel =
+            |s: &Arc<Vits2Sessions>| s.parallel_synthesis == self.parallel_synthesis;
+
+        match &self.models {
+            EitherTtsModelVec::Limit(vec) => {
+                let same_models: Vec<_> = vec
+                    .iter()
+                    .filter(|m| m.model_hash == *model_hash && m.session_config == *session_config)
+                    .collect();
+
+                let source = same_models.first().map(|m| m.source.clone());
+                let sessions = same_models
+                    .iter()
+                    .filter_map(|m| m.sessions.clone())
+                    .find(same_parallel);
+                (sessions, source)
+            }
+
+            EitherTtsModelVec::NoLimit(vec) => {
+                let sessions = vec
+                    .iter()
+                    .filter(|m| m.model_hash == *model_hash && m.session_config == *session_config)
+                    .map(|m| m.sessions.clone())
+                    .find(same_parallel);
+                (sessions, None)
+            }
+        }
+    }
+
     fn get_either_model(&self, model_ident: &str) -> Option<EitherTtsModel> {
         let mut model = None;
 
@@ -356,20 +906,240 @@ impl TtsModelHolder {
         model
     }
 
+    // StyleSpec から重みを掛ける前のスタイルベクトルを求める
+    fn resolve_style(
+        &self,
+        style_vectors: &Array2<f32>,
+        style: &StyleSpec,
+    ) -> Result<Array1<f32>, Sbv2CoreError> {
+        let row = |style_id: i32| crate::style::get_style_row(style_vectors, style_id);
+
+        match style {
+            StyleSpec::Id(style_id) => row(*style_id),
+
+            StyleSpec::Mix(styles) => {
+                let mut weighted = vec![];
+                for (style_id, weight) in styles {
+                    weighted.push((row(*style_id)?, *weight));
+                }
+                crate::style::mix(&weighted)
+            }
+
+            StyleSpec::Lerp(from, to, t) => Ok(crate::style::lerp(&row(*from)?, &row(*to)?, *t)),
+
+            StyleSpec::Slerp(from, to, t) => Ok(crate::style::slerp(&row(*from)?, &row(*to)?, *t)),
+
+            StyleSpec::CrossModelMix(styles) => {
+                let mut weighted = vec![];
+                for (model_ident, style_id, weight) in styles {
+                    let other_style_vectors = self.get_style_vectors(model_ident)?;
+                    let style_vector = crate::style::get_style_row(other_style_vectors, *style_id)?;
+                    weighted.push((style_vector, *weight));
+                }
+                crate::style::mix(&weighted)
+            }
+        }
+    }
+
+    fn get_style_vectors(&self, model_ident: &str) -> Result<&Array2<f32>, Sbv2CoreError> {
+        let style_vectors = match self.get_either_model(model_ident) {
+            Some(EitherTtsModel::Limit(model)) => &model.style_vectors,
+            Some(EitherTtsModel::NoLimit(model)) => &model.style_vectors,
+            None => return Err(Sbv2CoreError::ModelNotFoundError(model_ident.to_string())),
+        };
+
+        Ok(style_vectors)
+    }
+
+    fn get_model_mut(
+        &mut self,
+        model_ident: &str,
+    ) -> Result<(&mut Array2<f32>, &mut ModelConfig), Sbv2CoreError> {
+        match &mut self.models {
+            EitherTtsModelVec::Limit(vec) => vec
+                .iter_mut()
+                .find(|m| m.model_ident == model_ident)
+                .map(|m| (&mut m.style_vectors, &mut m.config)),
+            EitherTtsModelVec::NoLimit(vec) => vec
+                .iter_mut()
+                .find(|m| m.model_ident == model_ident)
+                .map(|m| (&mut m.style_vectors, &mut m.config)),
+        }
+        .ok_or(Sbv2CoreError::ModelNotFoundError(model_ident.to_string()))
+    }
+
+    fn get_model_config(&self, model_ident: &str) -> Result<&ModelConfig, Sbv2CoreError> {
+        let config = match self.get_either_model(model_ident) {
+            Some(EitherTtsModel::Limit(model)) => &model.config,
+            Some(EitherTtsModel::NoLimit(model)) => &model.config,
+            None => return Err(Sbv2CoreError::ModelNotFoundError(model_ident.to_string())),
+        };
+
+        Ok(config)
+    }
+
     fn parse_text(
         &self,
         text: &str,
+        options: &SynthesizeOptions,
+    ) -> Result<(Array2<f32>, Array1<i64>, Array1<i64>, Array1<i64>), Sbv2CoreError> {
+        if options.given_tone.is_some()
+            && (options.mixed_language || options.language != Language::Jp)
+        {
+            return Err(Sbv2CoreError::ValueError(
+                "given_tone is only supported for Japanese text".to_string(),
+            ));
+        }
+
+        if !options.mixed_language {
+            return self.parse_text_lang(text, options.language, options);
+        }
+
+        // 言語ごとの区間に分けてそれぞれ解析し、一つの入力につなげる
+        let segments = crate::lang_segment::segment(text, options.language);
+        if let [(language, text)] = segments.as_slice() {
+            return self.parse_text_lang(text, *language, options);
+        }
+
+        let mut parsed = vec![];
+        for (language, text) in &segments {
+            parsed.push(self.parse_text_lang(text, *language, options)?);
+        }
+
+        crate::tts_util::concat_parsed(parsed)
+    }
+
+    // 分割した文をそれぞれ解析する
+    // (日本語の場合は BERT をバッチで推論し、`paragraph_context` の場合は段落全体で求める)
+    fn parse_lines(
+        &self,
+        texts: &[&str],
+        options: &SynthesizeOptions,
+    ) -> Result<Vec<ParsedText>, Sbv2CoreError> {
+        if options.paragraph_context && options.language == Language::Jp && !options.mixed_language
+        {
+            return crate::tts_util::parse_paragraph_blocking(
+                texts,
+                &self.jtalk,
+                &self.english_kana,
+                &self.tokenizer,
+                &options.parse_options(),
+                |token_ids, attention_masks| {
+                    self.bert_batcher
+                        .predict(&self.bert, token_ids, attention_masks)
+                },
+            );
+        }
+
+        if options.language == Language::Jp && !options.mixed_language {
+            return crate::tts_util::parse_texts_blocking(
+                texts,
+                &self.jtalk,
+                &self.english_kana,
+                &self.tokenizer,
+                &options.parse_options(),
+                |inputs| self.bert_batcher.predict_batch(&self.bert, inputs),
+            );
+        }
+
+        texts.iter().map(|t| self.parse_text(t, options)).collect()
+    }
+
+    fn parse_text_lang(
+        &self,
+        text: &str,
+        language: Language,
+        options: &SynthesizeOptions,
     ) -> Result<(Array2<f32>, Array1<i64>, Array1<i64>, Array1<i64>), Sbv2CoreError> {
-        crate::tts_util::parse_text_blocking(
+        let assist_text = options.assist_text_with_weight();
+
+        match language {
+            Language::Jp => crate::tts_util::parse_text_blocking(
+                text,
+                &self.jtalk,
+                &self.english_kana,
+                &self.tokenizer,
+                &options.parse_options(),
+                |token_ids, attention_masks| {
+                    self.bert_batcher
+                        .predict(&self.bert, token_ids, attention_masks)
+                },
+            ),
+
+            Language::En => {
+                let (bert, tokenizer) = self.get_lang_bert(Language::En)?;
+
+                crate::tts_util::parse_text_en_blocking(
+                    text,
+                    &self.english_g2p,
+                    tokenizer,
+                    assist_text,
+                    |token_ids, attention_masks| {
+                        self.bert_batcher.predict(bert, token_ids, attention_masks)
+                    },
+                )
+            }
+
+            Language::Zh => {
+                let (bert, tokenizer) = self.get_lang_bert(Language::Zh)?;
+
+                crate::tts_util::parse_text_zh_blocking(
+                    text,
+                    &self.chinese_g2p,
+                    tokenizer,
+                    assist_text,
+                    |token_ids, attention_masks| {
+                        self.bert_batcher.predict(bert, token_ids, attention_masks)
+                    },
+                )
+            }
+        }
+    }
+
+    fn get_lang_bert(&self, language: Language) -> Result<(&Session, &Tokenizer), Sbv2CoreError> {
+        let (bert, tokenizer) =
+            self.lang_berts
+                .get(&language)
+                .ok_or(Sbv2CoreError::ModelNotFoundError(format!(
+                    "{:?} bert",
+                    language
+                )))?;
+
+        Ok((bert, tokenizer))
+    }
+
+    /// 日本語のテキストの音素とアクセント (0 または 1) を返す
+    ///
+    /// アクセントを編集して `SynthesizeOptions::given_tone` に渡すと、そのアクセントで音声合成する
+    pub fn g2p(
+        &self,
+        text: &str,
+        options: &SynthesizeOptions,
+    ) -> Result<(Vec<String>, Vec<i32>), Sbv2CoreError> {
+        crate::tts_util::g2p_blocking(
             text,
             &self.jtalk,
-            &self.tokenizer,
-            |token_ids, attention_masks| {
-                crate::bert::predict(&self.bert, token_ids, attention_masks)
-            },
+            &self.english_kana,
+            &options.text_normalize,
         )
     }
 
+    /// `synthesize` と同じだが、スタイルと話者を config.json の名前で指定する
+    pub fn synthesize_by_name(
+        &mut self,
+        model_ident: &str,
+        text: &str,
+        style_name: &str,
+        speaker_name: &str,
+        options: SynthesizeOptions,
+    ) -> Result<Vec<u8>, Sbv2CoreError> {
+        let config = self.get_model_config(model_ident)?;
+        let style_id = config.style_id(style_name)?;
+        let speaker_id = config.speaker_id(speaker_name)?;
+
+        self.synthesize(model_ident, text, style_id, speaker_id, options)
+    }
+
     pub fn synthesize(
         &mut self,
         model_ident: &str,
@@ -377,58 +1147,154 @@ impl TtsModelHolder {
         style_id: i32,
         speaker_id: i64,
         options: SynthesizeOptions,
+    ) -> Result<Vec<u8>, Sbv2CoreError> {
+        self.synthesize_with_style(
+            model_ident,
+            text,
+            StyleSpec::Id(style_id),
+            speaker_id,
+            options,
+        )
+    }
+
+    /// `synthesize` と同じだが、スタイルの混合や補間を `StyleSpec` で指定する
+    pub fn synthesize_with_style(
+        &mut self,
+        model_ident: &str,
+        text: &str,
+        style: StyleSpec,
+        speaker_id: i64,
+        options: SynthesizeOptions,
     ) -> Result<Vec<u8>, Sbv2CoreError> {
         self.model_session_preparation(model_ident)?;
+        self.synthesize_loaded(model_ident, text, style, speaker_id, options)
+    }
 
+    /// `synthesize_with_style` と同じだが、モデルの読み込みを行わないため `&self` で呼び出せる
+    ///
+    /// 複数のスレッドから同時に呼び出すと、それぞれの文の BERT の推論をまとめてバッチで行う
+    /// (出力にバッチの次元がある BERT のみ)。
+    /// 読み込む数の上限があり、session が読み込まれていないモデルはエラーになる
+    pub fn synthesize_loaded(
+        &self,
+        model_ident: &str,
+        text: &str,
+        style: StyleSpec,
+        speaker_id: i64,
+        options: SynthesizeOptions,
+    ) -> Result<Vec<u8>, Sbv2CoreError> {
         let either_ttsmodel = self
             .get_either_model(model_ident)
             .ok_or(Sbv2CoreError::ModelNotFoundError(model_ident.to_string()))?;
 
-        let (vits2, style_vectors) = match either_ttsmodel {
+        let (vits2, vits2_pool, variant, style_vectors) = match either_ttsmodel {
             EitherTtsModel::Limit(upper_limit_tts_model) => {
-                let vits2 = upper_limit_tts_model.vits2.as_ref().expect("vits2 is None");
+                let sessions = upper_limit_tts_model.sessions.as_ref().ok_or(
+                    Sbv2CoreError::ModelNotFoundError(format!("{} (not loaded)", model_ident)),
+                )?;
                 let style_vectors = &upper_limit_tts_model.style_vectors;
 
-                (vits2, style_vectors)
+                (
+                    &sessions.vits2,
+                    &sessions.vits2_pool,
+                    upper_limit_tts_model.variant,
+                    style_vectors,
+                )
             }
 
             EitherTtsModel::NoLimit(no_upper_limit_tts_model) => {
-                let vits2 = &no_upper_limit_tts_model.vits2;
+                let sessions = &no_upper_limit_tts_model.sessions;
                 let style_vectors = &no_upper_limit_tts_model.style_vectors;
 
-                (vits2, style_vectors)
+                (
+                    &sessions.vits2,
+                    &sessions.vits2_pool,
+                    no_upper_limit_tts_model.variant,
+                    style_vectors,
+                )
             }
         };
+        variant.check_language(options.language, options.mixed_language)?;
+        let (noise_scale, noise_scale_w) = variant.default_noise_scales();
+
+        let style_vector = self.resolve_style(style_vectors, &style)?;
+        let params = SynthesisParams {
+            speaker_id,
+            style_vector: crate::style::apply_weight(
+                style_vectors,
+                style_vector,
+                options.style_weight,
+            )?,
+            sdp_ratio: options.sdp_ratio,
+            length_scale: options.length_scale,
+            noise_scale,
+            noise_scale_w,
+        };
 
-        let style_vector =
-            crate::style::get_style_vector(style_vectors, style_id, options.style_weight)?;
-
-        let audio_array = match options.split_sentences {
+        // given_tone はテキスト全体のアクセントなので分割しない
+        let audio_array = match options.split_sentences && options.given_tone.is_none() {
             true => {
                 let texts: Vec<&str> = text.split('\n').collect();
+                let lines: Vec<(usize, &str)> = texts
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, t)| !t.is_empty())
+                    .map(|(i, t)| (i, *t))
+                    .collect();
+                let line_texts: Vec<&str> = lines.iter().map(|(_, t)| *t).collect();
+
+                let sentence_audios = if !vits2_pool.is_empty() {
+                    // 文ごとに別の session で並列に合成する
+                    let parsed = self.parse_lines(&line_texts, &options)?;
+                    let sessions: Vec<&Session> =
+                        std::iter::once(vits2).chain(vits2_pool.iter()).collect();
+
+                    crate::model::synthesize_parallel(&sessions, variant, parsed, &params)?
+                } else {
+                    // バッチで推論できる場合は数文ずつ、段落全体で BERT を求める場合はすべての文をまとめて解析する
+                    let chunk_size = if options.paragraph_context {
+                        line_texts.len().max(1)
+                    } else if crate::model::vits2_supports_batch(vits2)
+                        || crate::model::bert_supports_batch(&self.bert)
+                    {
+                        PIPELINE_BATCH_SIZE
+                    } else {
+                        1
+                    };
+
+                    // 別スレッドで次の文の解析と BERT を進めながら VITS2 で合成する
+                    let this = self;
+                    let options = &options;
+                    let params = &params;
+
+                    std::thread::scope(|scope| {
+                        let (sender, receiver) = std::sync::mpsc::sync_channel(PIPELINE_DEPTH);
+
+                        scope.spawn(move || {
+                            for chunk in line_texts.chunks(chunk_size) {
+                                let parsed = this.parse_lines(chunk, options);
+                                let is_err = parsed.is_err();
+
+                                // 受信側がエラーで終了した場合も止める
+                                if sender.send(parsed).is_err() || is_err {
+                                    break;
+                                }
+                            }
+                        });
+
+                        let mut sentence_audios = vec![];
+                        for parsed in receiver {
+                            sentence_audios.extend(crate::model::synthesize_batch(
+                                vits2, variant, parsed?, params,
+                            )?);
+                        }
+
+                        Ok::<_, Sbv2CoreError>(sentence_audios)
+                    })?
+                };
 
                 let mut audios = vec![];
-                for (i, t) in texts.iter().enumerate() {
-                    if t.is_empty() {
-                        continue;
-                    }
-
-                    let (bert_ori, phones, tones, lang_ids) = self.parse_text(t)?;
-
-                    let audio = crate::model::synthesize(
-                        vits2,
-                        bert_ori.to_owned(),
-                        phones,
-                        Array1::from_vec(vec![speaker_id]),
-                        tones,
-                        lang_ids,
-                        style_vector.clone(),
-                        options.sdp_ratio,
-                        options.length_scale,
-                        0.677,
-                        0.8,
-                    )?;
-
+                for ((i, _), audio) in lines.into_iter().zip(sentence_audios) {
                     audios.push(audio);
                     if i != texts.len() - 1 {
                         audios.push(Array3::zeros((1, 1, 22050)));
@@ -442,20 +1308,8 @@ impl TtsModelHolder {
             }
 
             false => {
-                let (bert_ori, phones, tones, lang_ids) = self.parse_text(text)?;
-                crate::model::synthesize(
-                    vits2,
-                    bert_ori.to_owned(),
-                    phones,
-                    Array1::from_vec(vec![speaker_id]),
-                    tones,
-                    lang_ids,
-                    style_vector,
-                    options.sdp_ratio,
-                    options.length_scale,
-                    0.677,
-                    0.8,
-                )?
+                let parsed = self.parse_text(text, &options)?;
+                crate::model::synthesize(vits2, variant, parsed, &params)?
             }
         };
 
@@ -463,6 +1317,11 @@ impl TtsModelHolder {
     }
 }
 
+// 解析と BERT を先に進めておくまとまりの数
+const PIPELINE_DEPTH: usize = 2;
+// バッチで推論できる場合に一度に解析して合成する文の数
+const PIPELINE_BATCH_SIZE: usize = 8;
+
 /// Synthesize options
 ///
 /// # Fields
@@ -470,11 +1329,42 @@ impl TtsModelHolder {
 /// - `length_scale`: Length scale
 /// - `style_weight`: Style weight
 /// - `split_sentences`: Split sentences
+/// - `text_normalize`: Reading of numbers, dates, times, currency and units
+/// - `language`: Language of the text (with `mixed_language`, the language of kanji-only runs)
+/// - `mixed_language`: Split the text into Japanese/English/Chinese runs (for multilingual models)
+/// - `assist_text`: Text whose BERT features are blended in to steer emotion
+/// - `assist_text_weight`: Weight of `assist_text`
+/// - `given_tone`: Tones from `TtsModelHolder::g2p` to use instead of the computed ones (Japanese only)
+/// - `paragraph_context`: With `split_sentences`, compute BERT features over the whole paragraph (Japanese only)
 pub struct SynthesizeOptions {
     pub sdp_ratio: f32,
     pub length_scale: f32,
     pub style_weight: f32,
     pub split_sentences: bool,
+    pub text_normalize: TextNormalizeOptions,
+    pub language: Language,
+    pub mixed_language: bool,
+    pub assist_text: Option<String>,
+    pub assist_text_weight: f32,
+    pub given_tone: Option<Vec<i32>>,
+    pub paragraph_context: bool,
+}
+
+impl SynthesizeOptions {
+    fn assist_text_with_weight(&self) -> Option<(&str, f32)> {
+        self.assist_text
+            .as_deref()
+            .filter(|assist_text| !assist_text.is_empty())
+            .map(|assist_text| (assist_text, self.assist_text_weight))
+    }
+
+    fn parse_options(&self) -> ParseOptions<'_> {
+        ParseOptions {
+            text_normalize: &self.text_normalize,
+            assist_text: self.assist_text_with_weight(),
+            given_tone: self.given_tone.as_deref(),
+        }
+    }
 }
 
 impl Default for SynthesizeOptions {
@@ -484,6 +1374,91 @@ impl Default for SynthesizeOptions {
             length_scale: 1.0,
             style_weight: 1.0,
             split_sentences: true,
+            text_normalize: TextNormalizeOptions::default(),
+            language: Language::default(),
+            mixed_language: false,
+            assist_text: None,
+            assist_text_weight: 0.7,
+            given_tone: None,
+            paragraph_context: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Weak;
+
+    use super::*;
+
+    fn sessions(holder: &TtsModelHolder, model_ident: &str) -> Option<Arc<Vits2Sessions>> {
+        match holder.get_either_model(model_ident)? {
+            EitherTtsModel::Limit(model) => model.sessions.clone(),
+            EitherTtsModel::NoLimit(model) => Some(model.sessions.clone()),
+        }
+    }
+
+    // モデルが必要なので SBV2_BERT_PATH, SBV2_TOKENIZER_PATH, SBV2_MODEL_PATH (.sbv2) を指定して
+    // `cargo test -- --ignored` で実行する
+    #[test]
+    #[ignore]
+    fn share_sessions_between_idents() {
+        let path = |key: &str| std::env::var(key).unwrap_or_else(|_| panic!("{} is not set", key));
+        let sbv2_bytes = std::fs::read(path("SBV2_MODEL_PATH")).unwrap();
+
+        for max_loaded_models in [None, Some(1)] {
+            let mut holder = TtsModelHolder::new_from_filepath(
+                path("SBV2_BERT_PATH"),
+                path("SBV2_TOKENIZER_PATH"),
+                max_loaded_models,
+            )
+            .unwrap();
+            holder.load_from_sbv2file("a", &sbv2_bytes).unwrap();
+            holder.load_from_sbv2file("b", &sbv2_bytes).unwrap();
+
+            // 共有している session は一つと数え、上限に達していても共有できる
+            let a = sessions(&holder, "a").unwrap();
+            let b = sessions(&holder, "b").unwrap();
+            assert!(Arc::ptr_eq(&a, &b));
+            assert_eq!(holder.get_loadedmodel_count(), 1);
+
+            let weak: Weak<Vits2Sessions> = Arc::downgrade(&a);
+            drop((a, b));
+
+            assert!(holder.unload("a"));
+            holder
+                .synthesize("b", "こんにちは", 0, 0, SynthesizeOptions::default())
+                .unwrap();
+            assert!(weak.upgrade().is_some());
+
+            assert!(holder.unload("b"));
+            assert!(weak.upgrade().is_none());
+            assert_eq!(holder.get_loadedmodel_count(), 0);
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn reload_sessions_when_parallel_synthesis_changes() {
+        let path = |key: &str| std::env::var(key).unwrap_or_else(|_| panic!("{} is not set", key));
+        let sbv2_bytes = std::fs::read(path("SBV2_MODEL_PATH")).unwrap();
+
+        let mut holder = TtsModelHolder::new_from_filepath(
+            path("SBV2_BERT_PATH"),
+            path("SBV2_TOKENIZER_PATH"),
+            None,
+        )
+        .unwrap();
+        holder.load_from_sbv2file("a", &sbv2_bytes).unwrap();
+
+        // 並列合成の設定が違う session は共有しない
+        holder.set_parallel_synthesis(Some(ParallelSynthesisConfig::with_workers(2)));
+        holder.load_from_sbv2file("b", &sbv2_bytes).unwrap();
+
+        let a = sessions(&holder, "a").unwrap();
+        let b = sessions(&holder, "b").unwrap();
+        assert!(!Arc::ptr_eq(&a, &b));
+        assert_eq!(b.vits2_pool.len(), 1);
+        assert_eq!(holder.get_loadedmodel_count(), 2);
+    }
+}
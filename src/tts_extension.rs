@@ -1,6 +1,8 @@
 use std::path::PathBuf;
 
-use crate::{errors::Sbv2CoreError, TtsModelHolder};
+use crate::{
+    errors::Sbv2CoreError, model_storage::ModelOrigin, Language, SessionConfig, TtsModelHolder,
+};
 
 pub trait TtsModelHolderFromPath {
     fn load_from_path<P>(
@@ -12,6 +14,17 @@ pub trait TtsModelHolderFromPath {
     where
         P: Into<PathBuf>;
 
+    /// `load_from_path` と同じだが、このモデルの VITS2 の session の設定を指定する
+    fn load_from_path_with_session_config<P>(
+        &mut self,
+        model_ident: &str,
+        style_vectors_path: P,
+        vits2_path: P,
+        session_config: SessionConfig,
+    ) -> Result<(), Sbv2CoreError>
+    where
+        P: Into<PathBuf>;
+
     fn load_from_sbv2file_path<P>(
         &mut self,
         model_ident: &str,
@@ -19,6 +32,41 @@ pub trait TtsModelHolderFromPath {
     ) -> Result<(), Sbv2CoreError>
     where
         P: Into<PathBuf>;
+
+    /// `load_from_sbv2file_path` と同じだが、このモデルの VITS2 の session の設定を指定する
+    fn load_from_sbv2file_path_with_session_config<P>(
+        &mut self,
+        model_ident: &str,
+        sbv2_path: P,
+        session_config: SessionConfig,
+    ) -> Result<(), Sbv2CoreError>
+    where
+        P: Into<PathBuf>;
+
+    fn load_config_from_path<P>(
+        &mut self,
+        model_ident: &str,
+        config_path: P,
+    ) -> Result<(), Sbv2CoreError>
+    where
+        P: Into<PathBuf>;
+
+    fn load_bert_from_path<P>(
+        &mut self,
+        language: Language,
+        bert_model_path: P,
+        tokenizer_path: P,
+    ) -> Result<(), Sbv2CoreError>
+    where
+        P: Into<PathBuf>;
+
+    fn load_cmudict_from_path<P>(&mut self, cmudict_path: P) -> Result<(), Sbv2CoreError>
+    where
+        P: Into<PathBuf>;
+
+    fn load_pinyin_dict_from_path<P>(&mut self, pinyin_dict_path: P) -> Result<(), Sbv2CoreError>
+    where
+        P: Into<PathBuf>;
 }
 
 impl TtsModelHolderFromPath for TtsModelHolder {
@@ -28,6 +76,24 @@ impl TtsModelHolderFromPath for TtsModelHolder {
         style_vectors_path: P,
         vits2_path: P,
     ) -> Result<(), Sbv2CoreError>
+    where
+        P: Into<PathBuf>,
+    {
+        self.load_from_path_with_session_config(
+            model_ident,
+            style_vectors_path,
+            vits2_path,
+            self.vits2_session_config(),
+        )
+    }
+
+    fn load_from_path_with_session_config<P>(
+        &mut self,
+        model_ident: &str,
+        style_vectors_path: P,
+        vits2_path: P,
+        session_config: SessionConfig,
+    ) -> Result<(), Sbv2CoreError>
     where
         P: Into<PathBuf>,
     {
@@ -35,9 +101,15 @@ impl TtsModelHolderFromPath for TtsModelHolder {
         let vits2_path: PathBuf = vits2_path.into();
 
         let style_vectors_bytes = std::fs::read(style_vectors_path)?;
-        let vits2_bytes = std::fs::read(vits2_path)?;
+        let vits2_bytes = std::fs::read(&vits2_path)?;
 
-        self.load(model_ident, style_vectors_bytes, vits2_bytes)
+        self.load_with_origin(
+            model_ident,
+            style_vectors_bytes,
+            vits2_bytes,
+            ModelOrigin::OnnxPath(vits2_path),
+            session_config,
+        )
     }
 
     fn load_from_sbv2file_path<P>(
@@ -45,12 +117,85 @@ impl TtsModelHolderFromPath for TtsModelHolder {
         model_ident: &str,
         sbv2_path: P,
     ) -> Result<(), Sbv2CoreError>
+    where
+        P: Into<PathBuf>,
+    {
+        self.load_from_sbv2file_path_with_session_config(
+            model_ident,
+            sbv2_path,
+            self.vits2_session_config(),
+        )
+    }
+
+    fn load_from_sbv2file_path_with_session_config<P>(
+        &mut self,
+        model_ident: &str,
+        sbv2_path: P,
+        session_config: SessionConfig,
+    ) -> Result<(), Sbv2CoreError>
     where
         P: Into<PathBuf>,
     {
         let sbv2_path: PathBuf = sbv2_path.into();
-        let sbv2_bytes = std::fs::read(sbv2_path)?;
+        let sbv2_bytes = std::fs::read(&sbv2_path)?;
+
+        self.load_sbv2_with_origin(
+            model_ident,
+            &sbv2_bytes,
+            ModelOrigin::Sbv2Path(sbv2_path, &sbv2_bytes),
+            session_config,
+        )
+    }
+
+    fn load_config_from_path<P>(
+        &mut self,
+        model_ident: &str,
+        config_path: P,
+    ) -> Result<(), Sbv2CoreError>
+    where
+        P: Into<PathBuf>,
+    {
+        let config_path: PathBuf = config_path.into();
+        let config_bytes = std::fs::read(config_path)?;
+
+        self.load_config(model_ident, config_bytes)
+    }
+
+    fn load_bert_from_path<P>(
+        &mut self,
+        language: Language,
+        bert_model_path: P,
+        tokenizer_path: P,
+    ) -> Result<(), Sbv2CoreError>
+    where
+        P: Into<PathBuf>,
+    {
+        let bert_model_path: PathBuf = bert_model_path.into();
+        let tokenizer_path: PathBuf = tokenizer_path.into();
+
+        let bert_model_bytes = std::fs::read(bert_model_path)?;
+        let tokenizer_bytes = std::fs::read(tokenizer_path)?;
+
+        self.load_bert(language, bert_model_bytes, tokenizer_bytes)
+    }
+
+    fn load_cmudict_from_path<P>(&mut self, cmudict_path: P) -> Result<(), Sbv2CoreError>
+    where
+        P: Into<PathBuf>,
+    {
+        let cmudict_path: PathBuf = cmudict_path.into();
+        let cmudict_bytes = std::fs::read(cmudict_path)?;
+
+        self.load_cmudict(cmudict_bytes)
+    }
+
+    fn load_pinyin_dict_from_path<P>(&mut self, pinyin_dict_path: P) -> Result<(), Sbv2CoreError>
+    where
+        P: Into<PathBuf>,
+    {
+        let pinyin_dict_path: PathBuf = pinyin_dict_path.into();
+        let pinyin_dict_bytes = std::fs::read(pinyin_dict_path)?;
 
-        self.load_from_sbv2file(model_ident, sbv2_bytes)
+        self.load_pinyin_dict(pinyin_dict_bytes)
     }
 }
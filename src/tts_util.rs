@@ -4,20 +4,240 @@ use hound::{SampleFormat, WavSpec, WavWriter};
 use ndarray::{s, Array, Array1, Array2, Array3, Axis};
 use tokenizers::Tokenizer;
 
-use crate::{errors::Sbv2CoreError, jtalk::JTalk};
+use crate::{
+    chinese::ChineseG2p,
+    english::EnglishG2p,
+    english_kana::EnglishKana,
+    errors::Sbv2CoreError,
+    jtalk::{JTalk, JTalkProcess},
+    nlp::Language,
+    text_norm::TextNormalizeOptions,
+};
+
+// (音素単位の BERT の特徴量, 音素, アクセント, 言語 ID)
+pub type ParsedText = (Array2<f32>, Array1<i64>, Array1<i64>, Array1<i64>);
+
+/// 日本語のテキストの解析の設定
+///
+/// # Fields
+/// - `text_normalize`: Reading of numbers, dates, times, currency and units
+/// - `assist_text`: Text whose BERT features are blended in, with its weight
+/// - `given_tone`: Tones to use instead of the computed ones (single text only)
+pub struct ParseOptions<'a> {
+    pub text_normalize: &'a TextNormalizeOptions,
+    pub assist_text: Option<(&'a str, f32)>,
+    pub given_tone: Option<&'a [i32]>,
+}
 
 pub fn parse_text_blocking(
     text: &str,
     jtalk: &JTalk,
+    english_kana: &EnglishKana,
     tokenizer: &Tokenizer,
-    bert_predict: impl FnOnce(Vec<i64>, Vec<i64>) -> Result<ndarray::Array2<f32>, Sbv2CoreError>,
+    options: &ParseOptions,
+    bert_predict: impl Fn(Vec<i64>, Vec<i64>) -> Result<ndarray::Array2<f32>, Sbv2CoreError>,
 ) -> Result<(Array2<f32>, Array1<i64>, Array1<i64>, Array1<i64>), Sbv2CoreError> {
-    let text = jtalk.num2word(text)?;
-    let normalized_text = crate::norm::normalize_text(&text);
+    let parsed = parse_phones_jp(
+        text,
+        jtalk,
+        english_kana,
+        options.text_normalize,
+        options.given_tone,
+    )?;
 
-    let process = jtalk.process_text(&normalized_text)?;
-    let (phones, tones, mut word2ph) = process.g2p()?;
-    let (phones, tones, lang_ids) = crate::nlp::cleaned_text_to_sequence(phones, tones);
+    let (token_ids, attention_masks) = crate::tokenizer::tokenize(&parsed.seq_text, tokenizer)?;
+    let bert_content = bert_predict(token_ids, attention_masks)?;
+
+    let assist_content = match options.assist_text {
+        Some((assist_text, weight)) => {
+            let (token_ids, attention_masks) = crate::tokenizer::tokenize(assist_text, tokenizer)?;
+            Some((bert_predict(token_ids, attention_masks)?, weight))
+        }
+        None => None,
+    };
+
+    parsed.into_output(&bert_content, assist_content.as_ref())
+}
+
+/// 複数の文を解析し、BERT の特徴量はまとめて (バッチで) 求める
+pub fn parse_texts_blocking(
+    texts: &[&str],
+    jtalk: &JTalk,
+    english_kana: &EnglishKana,
+    tokenizer: &Tokenizer,
+    options: &ParseOptions,
+    bert_predict_batch: impl Fn(
+        Vec<(Vec<i64>, Vec<i64>)>,
+    ) -> Result<Vec<ndarray::Array2<f32>>, Sbv2CoreError>,
+) -> Result<Vec<ParsedText>, Sbv2CoreError> {
+    reject_given_tone(options)?;
+    let assist_text = options.assist_text;
+
+    let mut parsed = vec![];
+    let mut bert_inputs = vec![];
+    for text in texts {
+        let p = parse_phones_jp(text, jtalk, english_kana, options.text_normalize, None)?;
+        bert_inputs.push(crate::tokenizer::tokenize(&p.seq_text, tokenizer)?);
+        parsed.push(p);
+    }
+
+    // assist_text も同じバッチで推論する
+    if let Some((assist_text, _)) = assist_text {
+        bert_inputs.push(crate::tokenizer::tokenize(assist_text, tokenizer)?);
+    }
+
+    let mut bert_contents = bert_predict_batch(bert_inputs)?;
+    let assist_content = match assist_text {
+        Some((_, weight)) => bert_contents.pop().map(|content| (content, weight)),
+        None => None,
+    };
+
+    parsed
+        .into_iter()
+        .zip(bert_contents)
+        .map(|(p, bert_content)| p.into_output(&bert_content, assist_content.as_ref()))
+        .collect()
+}
+
+/// 複数の文の BERT の特徴量を段落全体で一度に求め、文ごとに解析する
+///
+/// 段落全体が BERT の入力の上限を超える場合は文ごとに求める
+pub fn parse_paragraph_blocking(
+    texts: &[&str],
+    jtalk: &JTalk,
+    english_kana: &EnglishKana,
+    tokenizer: &Tokenizer,
+    options: &ParseOptions,
+    bert_predict: impl Fn(Vec<i64>, Vec<i64>) -> Result<ndarray::Array2<f32>, Sbv2CoreError>,
+) -> Result<Vec<ParsedText>, Sbv2CoreError> {
+    reject_given_tone(options)?;
+
+    let mut parsed = vec![];
+    for text in texts {
+        parsed.push(parse_phones_jp(
+            text,
+            jtalk,
+            english_kana,
+            options.text_normalize,
+            None,
+        )?);
+    }
+
+    let assist_content = match options.assist_text {
+        Some((assist_text, weight)) => {
+            let (token_ids, attention_masks) = crate::tokenizer::tokenize(assist_text, tokenizer)?;
+            Some((bert_predict(token_ids, attention_masks)?, weight))
+        }
+        None => None,
+    };
+
+    let paragraph: String = parsed.iter().map(|p| p.seq_text.as_str()).collect();
+    let (token_ids, attention_masks) = crate::tokenizer::tokenize(&paragraph, tokenizer)?;
+
+    // 一文字一トークンになっていない場合も文ごとに求める
+    if token_ids.len() > MAX_BERT_TOKENS || token_ids.len() != paragraph.chars().count() + 2 {
+        let mut results = vec![];
+        for p in parsed {
+            let (token_ids, attention_masks) = crate::tokenizer::tokenize(&p.seq_text, tokenizer)?;
+            let bert_content = bert_predict(token_ids, attention_masks)?;
+            results.push(p.into_output(&bert_content, assist_content.as_ref())?);
+        }
+        return Ok(results);
+    }
+
+    let paragraph_content = bert_predict(token_ids, attention_masks)?;
+    let lens: Vec<usize> = parsed.iter().map(|p| p.seq_text.chars().count()).collect();
+    let bert_contents = slice_paragraph_content(&paragraph_content, &lens)?;
+
+    parsed
+        .into_iter()
+        .zip(bert_contents)
+        .map(|(p, bert_content)| p.into_output(&bert_content, assist_content.as_ref()))
+        .collect()
+}
+
+// 段落全体の特徴量を文ごとに切り出し、[CLS] と [SEP] の特徴量で挟む
+fn slice_paragraph_content(
+    paragraph_content: &Array2<f32>,
+    lens: &[usize],
+) -> Result<Vec<Array2<f32>>, Sbv2CoreError> {
+    let rows = paragraph_content.nrows();
+    if rows != lens.iter().sum::<usize>() + 2 {
+        return Err(Sbv2CoreError::ValueError(format!(
+            "paragraph BERT output has {} rows, expected {}",
+            rows,
+            lens.iter().sum::<usize>() + 2
+        )));
+    }
+
+    let last = rows - 1;
+    let mut contents = vec![];
+    let mut offset = 1;
+    for len in lens {
+        contents.push(ndarray::concatenate(
+            Axis(0),
+            &[
+                paragraph_content.slice(s![0..1, ..]),
+                paragraph_content.slice(s![offset..offset + len, ..]),
+                paragraph_content.slice(s![last..last + 1, ..]),
+            ],
+        )?);
+        offset += len;
+    }
+
+    Ok(contents)
+}
+
+// 日本語の BERT の入力の上限
+const MAX_BERT_TOKENS: usize = 512;
+
+// BERT の特徴量を求める前の日本語の解析結果
+struct ParsedPhonesJp {
+    seq_text: String,
+    phones: Vec<i64>,
+    tones: Vec<i64>,
+    lang_ids: Vec<i64>,
+    word2ph: Vec<i32>,
+}
+
+impl ParsedPhonesJp {
+    fn into_output(
+        self,
+        bert_content: &Array2<f32>,
+        assist_content: Option<&(Array2<f32>, f32)>,
+    ) -> Result<(Array2<f32>, Array1<i64>, Array1<i64>, Array1<i64>), Sbv2CoreError> {
+        let mut bert_ori = phone_level_feature(bert_content, &self.word2ph)?;
+
+        if let Some((assist_content, weight)) = assist_content {
+            bert_ori = blend_assist_text(bert_ori, assist_content, *weight);
+        }
+
+        Ok((
+            bert_ori,
+            self.phones.into(),
+            self.tones.into(),
+            self.lang_ids.into(),
+        ))
+    }
+}
+
+fn parse_phones_jp(
+    text: &str,
+    jtalk: &JTalk,
+    english_kana: &EnglishKana,
+    normalize_options: &TextNormalizeOptions,
+    given_tone: Option<&[i32]>,
+) -> Result<ParsedPhonesJp, Sbv2CoreError> {
+    let (normalized_text, process) = jtalk_process(text, jtalk, english_kana, normalize_options)?;
+    let (phones, mut tones, mut word2ph) = process.g2p()?;
+
+    if let Some(given_tone) = given_tone {
+        validate_given_tone(given_tone, tones.len())?;
+        tones = given_tone.to_vec();
+    }
+
+    let (phones, tones, lang_ids) =
+        crate::nlp::cleaned_text_to_sequence(phones, tones, Language::Jp)?;
 
     let phones = crate::utils::intersperse(&phones, 0);
     let tones = crate::utils::intersperse(&tones, 0);
@@ -28,21 +248,233 @@ pub fn parse_text_blocking(
     }
     word2ph[0] += 1;
 
-    let text = {
+    let seq_text = {
         let (seq_text, _) = process.text_to_seq_kata()?;
         seq_text.join("")
     };
-    let (token_ids, attention_masks) = crate::tokenizer::tokenize(&text, tokenizer)?;
 
     assert!(
-        word2ph.len() == text.chars().count() + 2,
+        word2ph.len() == seq_text.chars().count() + 2,
         "{} {}",
         word2ph.len(),
         normalized_text.chars().count()
     );
 
+    Ok(ParsedPhonesJp {
+        seq_text,
+        phones,
+        tones,
+        lang_ids,
+        word2ph,
+    })
+}
+
+/// 日本語のテキストの音素とアクセント (0 または 1) を返す
+///
+/// アクセントを編集して `SynthesizeOptions::given_tone` に渡すことができる
+pub fn g2p_blocking(
+    text: &str,
+    jtalk: &JTalk,
+    english_kana: &EnglishKana,
+    normalize_options: &TextNormalizeOptions,
+) -> Result<(Vec<String>, Vec<i32>), Sbv2CoreError> {
+    let (_, process) = jtalk_process(text, jtalk, english_kana, normalize_options)?;
+    let (phones, tones, _) = process.g2p()?;
+
+    Ok((phones, tones))
+}
+
+// 日本語のテキストを正規化して解析する
+fn jtalk_process(
+    text: &str,
+    jtalk: &JTalk,
+    english_kana: &EnglishKana,
+    normalize_options: &TextNormalizeOptions,
+) -> Result<(String, JTalkProcess), Sbv2CoreError> {
+    let text = crate::text_norm::normalize_numbers(text, normalize_options);
+    let text = english_kana.convert(&text);
+    let text = jtalk.num2word(&text)?;
+    let normalized_text = crate::norm::normalize_text(&text);
+
+    let process = jtalk.process_text(&normalized_text)?;
+
+    Ok((normalized_text, process))
+}
+
+// given_tone はテキスト全体のアクセントなので、複数の文をまとめて解析する場合は使えない
+fn reject_given_tone(options: &ParseOptions) -> Result<(), Sbv2CoreError> {
+    if options.given_tone.is_some() {
+        return Err(Sbv2CoreError::ValueError(
+            "given_tone cannot be used with multiple sentences".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn validate_given_tone(given_tone: &[i32], phone_len: usize) -> Result<(), Sbv2CoreError> {
+    if given_tone.len() != phone_len {
+        return Err(Sbv2CoreError::ValueError(format!(
+            "given_tone length {} does not match phone length {}",
+            given_tone.len(),
+            phone_len
+        )));
+    }
+
+    if let Some((i, tone)) = given_tone
+        .iter()
+        .enumerate()
+        .find(|(_, tone)| !matches!(tone, 0 | 1))
+    {
+        return Err(Sbv2CoreError::ValueError(format!(
+            "given_tone[{}] is {}, expected 0 or 1",
+            i, tone
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn parse_text_en_blocking(
+    text: &str,
+    english_g2p: &EnglishG2p,
+    tokenizer: &Tokenizer,
+    assist_text: Option<(&str, f32)>,
+    bert_predict: impl Fn(Vec<i64>, Vec<i64>) -> Result<ndarray::Array2<f32>, Sbv2CoreError>,
+) -> Result<(Array2<f32>, Array1<i64>, Array1<i64>, Array1<i64>), Sbv2CoreError> {
+    let normalized_text = crate::english::normalize_text(text);
+
+    let (token_ids, attention_masks, words) =
+        crate::tokenizer::tokenize_words(&normalized_text, tokenizer)?;
+
+    let (phones, tones, mut word2ph) = english_g2p.g2p(&words);
+    let (phones, tones, lang_ids) =
+        crate::nlp::cleaned_text_to_sequence(phones, tones, Language::En)?;
+
+    if word2ph.len() != token_ids.len() {
+        return Err(Sbv2CoreError::ValueError(format!(
+            "word2ph length {} does not match token length {}: {}",
+            word2ph.len(),
+            token_ids.len(),
+            normalized_text
+        )));
+    }
+
+    let phones = crate::utils::intersperse(&phones, 0);
+    let tones = crate::utils::intersperse(&tones, 0);
+    let lang_ids = crate::utils::intersperse(&lang_ids, 0);
+
+    for item in &mut word2ph {
+        *item *= 2;
+    }
+    word2ph[0] += 1;
+
     let bert_content = bert_predict(token_ids, attention_masks)?;
+    let mut bert_ori = phone_level_feature(&bert_content, &word2ph)?;
+
+    if let Some((assist_text, weight)) = assist_text {
+        let (token_ids, attention_masks, _) =
+            crate::tokenizer::tokenize_words(assist_text, tokenizer)?;
+        let assist_content = bert_predict(token_ids, attention_masks)?;
+        bert_ori = blend_assist_text(bert_ori, &assist_content, weight);
+    }
+
+    Ok((bert_ori, phones.into(), tones.into(), lang_ids.into()))
+}
+
+pub fn parse_text_zh_blocking(
+    text: &str,
+    chinese_g2p: &ChineseG2p,
+    tokenizer: &Tokenizer,
+    assist_text: Option<(&str, f32)>,
+    bert_predict: impl Fn(Vec<i64>, Vec<i64>) -> Result<ndarray::Array2<f32>, Sbv2CoreError>,
+) -> Result<(Array2<f32>, Array1<i64>, Array1<i64>, Array1<i64>), Sbv2CoreError> {
+    let normalized_text = crate::chinese::normalize_text(text);
+
+    // 中国語の BERT は一文字ずつトークンになる
+    let (token_ids, attention_masks, _) =
+        crate::tokenizer::tokenize_words(&normalized_text, tokenizer)?;
+
+    let (phones, tones, mut word2ph) = chinese_g2p.g2p(&normalized_text)?;
+    let (phones, tones, lang_ids) =
+        crate::nlp::cleaned_text_to_sequence(phones, tones, Language::Zh)?;
+
+    if word2ph.len() != token_ids.len() {
+        return Err(Sbv2CoreError::ValueError(format!(
+            "word2ph length {} does not match token length {}: {}",
+            word2ph.len(),
+            token_ids.len(),
+            normalized_text
+        )));
+    }
+
+    let phones = crate::utils::intersperse(&phones, 0);
+    let tones = crate::utils::intersperse(&tones, 0);
+    let lang_ids = crate::utils::intersperse(&lang_ids, 0);
+
+    for item in &mut word2ph {
+        *item *= 2;
+    }
+    word2ph[0] += 1;
+
+    let bert_content = bert_predict(token_ids, attention_masks)?;
+    let mut bert_ori = phone_level_feature(&bert_content, &word2ph)?;
+
+    if let Some((assist_text, weight)) = assist_text {
+        let (token_ids, attention_masks, _) =
+            crate::tokenizer::tokenize_words(assist_text, tokenizer)?;
+        let assist_content = bert_predict(token_ids, attention_masks)?;
+        bert_ori = blend_assist_text(bert_ori, &assist_content, weight);
+    }
+
+    Ok((bert_ori, phones.into(), tones.into(), lang_ids.into()))
+}
+
+/// 言語ごとに解析した結果を一つの入力につなげる
+///
+/// `intersperse` した音素は `_, 0, ..., _, 0, 0` となっているので、先頭以外は最初の `_, 0` を、
+/// 末尾以外は最後の `_, 0, 0` を取り除く
+pub fn concat_parsed(
+    parsed: Vec<ParsedText>,
+) -> Result<(Array2<f32>, Array1<i64>, Array1<i64>, Array1<i64>), Sbv2CoreError> {
+    let last = parsed.len().saturating_sub(1);
+
+    let mut berts = vec![];
+    let (mut phones, mut tones, mut lang_ids) = (vec![], vec![], vec![]);
+
+    for (i, (bert, p, t, l)) in parsed.into_iter().enumerate() {
+        let start = if i == 0 { 0 } else { 2 };
+        let end = if i == last { p.len() } else { p.len() - 3 };
+
+        berts.push(bert.slice(s![.., start..end]).to_owned());
+        phones.extend_from_slice(&p.as_slice().unwrap()[start..end]);
+        tones.extend_from_slice(&t.as_slice().unwrap()[start..end]);
+        lang_ids.extend_from_slice(&l.as_slice().unwrap()[start..end]);
+    }
+
+    let bert = ndarray::concatenate(Axis(1), &berts.iter().map(|x| x.view()).collect::<Vec<_>>())?;
 
+    Ok((bert, phones.into(), tones.into(), lang_ids.into()))
+}
+
+// assist_text の BERT の特徴量の平均を、音素単位の特徴量に weight の割合で混ぜる
+fn blend_assist_text(
+    bert_ori: Array2<f32>,
+    assist_content: &Array2<f32>,
+    weight: f32,
+) -> Array2<f32> {
+    let Some(assist_mean) = assist_content.mean_axis(Axis(0)) else {
+        return bert_ori;
+    };
+
+    bert_ori * (1.0 - weight) + &(assist_mean.insert_axis(Axis(1)) * weight)
+}
+
+// 文字 (トークン) 単位の BERT の特徴量を word2ph に従って音素単位に展開する
+fn phone_level_feature(
+    bert_content: &Array2<f32>,
+    word2ph: &[i32],
+) -> Result<Array2<f32>, Sbv2CoreError> {
     let mut phone_level_feature = vec![];
     for (i, reps) in word2ph.iter().enumerate() {
         let repeat_feature = {
@@ -70,13 +502,7 @@ pub fn parse_text_blocking(
             .collect::<Vec<_>>(),
     )?;
 
-    let bert_ori = phone_level_feature.t();
-    Ok((
-        bert_ori.to_owned(),
-        phones.into(),
-        tones.into(),
-        lang_ids.into(),
-    ))
+    Ok(phone_level_feature.t().to_owned())
 }
 
 pub fn array_to_vec(audio_array: Array3<f32>) -> Result<Vec<u8>, Sbv2CoreError> {
@@ -100,3 +526,137 @@ pub fn array_to_vec(audio_array: Array3<f32>) -> Result<Vec<u8>, Sbv2CoreError>
 
     Ok(cursor.into_inner())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 音素 `_ ...phones _` (`_` は 7 とする) を intersperse したものと、各列に音素の値を入れた BERT の特徴量
+    fn parsed(phones: &[i64], lang_id: i64) -> ParsedText {
+        let phones: Vec<i64> = [7].iter().chain(phones).chain(&[7]).copied().collect();
+        let phones = crate::utils::intersperse(&phones, 0);
+
+        let bert = Array2::from_shape_fn((2, phones.len()), |(_, j)| phones[j] as f32);
+        let tones = Array1::zeros(phones.len());
+        let lang_ids = Array1::from_elem(phones.len(), lang_id);
+
+        (bert, phones.into(), tones, lang_ids)
+    }
+
+    #[test]
+    fn concat_parsed_joins_segments() {
+        let (bert, phones, tones, lang_ids) =
+            concat_parsed(vec![parsed(&[11, 12], 1), parsed(&[21], 2)]).unwrap();
+
+        assert_eq!(phones.to_vec(), [7, 0, 11, 0, 12, 0, 21, 0, 7, 0, 0]);
+        assert_eq!(lang_ids.to_vec(), [1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2]);
+        assert_eq!(tones.len(), phones.len());
+
+        assert_eq!(bert.ncols(), phones.len());
+        let row: Vec<i64> = bert.row(1).iter().map(|x| *x as i64).collect();
+        assert_eq!(row, phones.to_vec());
+    }
+
+    #[test]
+    fn concat_parsed_keeps_single_segment() {
+        let (bert, phones, _, _) = concat_parsed(vec![parsed(&[11], 0)]).unwrap();
+
+        assert_eq!(phones.to_vec(), [7, 0, 11, 0, 7, 0, 0]);
+        assert_eq!(bert.ncols(), phones.len());
+    }
+
+    #[test]
+    fn concat_parsed_three_segments() {
+        let segments = vec![parsed(&[11], 1), parsed(&[21, 22], 2), parsed(&[31], 0)];
+        let total: usize = [7 - 3, 9 - 5, 7 - 2].iter().sum();
+
+        let (bert, phones, tones, lang_ids) = concat_parsed(segments).unwrap();
+
+        assert_eq!(phones.len(), total);
+        assert_eq!(tones.len(), total);
+        assert_eq!(lang_ids.len(), total);
+        assert_eq!(bert.ncols(), total);
+        assert_eq!(phones.to_vec(), [7, 0, 11, 0, 21, 0, 22, 0, 31, 0, 7, 0, 0]);
+    }
+
+    #[test]
+    fn validate_given_tone_accepts_matching_tones() {
+        assert!(validate_given_tone(&[0, 1, 1, 0], 4).is_ok());
+        assert!(validate_given_tone(&[], 0).is_ok());
+    }
+
+    #[test]
+    fn validate_given_tone_rejects_length_mismatch() {
+        let err = validate_given_tone(&[0, 1, 1], 4).unwrap_err();
+        assert!(err.to_string().contains("length 3"));
+    }
+
+    #[test]
+    fn validate_given_tone_rejects_out_of_range_tone() {
+        let err = validate_given_tone(&[0, 1, 2, 0], 4).unwrap_err();
+        assert!(err.to_string().contains("given_tone[2] is 2"));
+
+        assert!(validate_given_tone(&[-1], 1).is_err());
+    }
+
+    // word2ph (前後の [CLS] と [SEP] を含む) から parse_phones_jp と同じ形の解析結果を作る
+    fn parsed_phones(seq_text: &str, word2ph: &[i32]) -> ParsedPhonesJp {
+        let phone_len = word2ph.iter().sum::<i32>() as usize;
+        let phones = crate::utils::intersperse(&vec![1; phone_len], 0);
+
+        let mut word2ph: Vec<i32> = word2ph.iter().map(|n| n * 2).collect();
+        word2ph[0] += 1;
+
+        ParsedPhonesJp {
+            seq_text: seq_text.to_string(),
+            tones: vec![0; phones.len()],
+            lang_ids: vec![1; phones.len()],
+            phones,
+            word2ph,
+        }
+    }
+
+    #[test]
+    fn slice_paragraph_content_per_sentence() {
+        let sentences = [
+            parsed_phones("アイ", &[1, 2, 1, 1]),
+            parsed_phones("ウ", &[1, 3, 1]),
+            parsed_phones("エオカ", &[1, 1, 2, 2, 1]),
+        ];
+        let lens: Vec<usize> = sentences
+            .iter()
+            .map(|p| p.seq_text.chars().count())
+            .collect();
+
+        // 行ごとに異なる値を入れた [CLS] 文1 文2 文3 [SEP] の特徴量
+        let paragraph_content = Array2::from_shape_fn((8, 3), |(i, _)| i as f32);
+        let contents = slice_paragraph_content(&paragraph_content, &lens).unwrap();
+
+        // [CLS] と [SEP] を除いた幅の合計は段落の幅と一致する
+        let widths: Vec<usize> = contents.iter().map(|c| c.nrows()).collect();
+        assert_eq!(widths, [4, 3, 5]);
+        assert_eq!(
+            widths.iter().map(|w| w - 2).sum::<usize>() + 2,
+            paragraph_content.nrows()
+        );
+
+        let rows = |c: &Array2<f32>| -> Vec<f32> { c.column(0).to_vec() };
+        assert_eq!(rows(&contents[0]), [0.0, 1.0, 2.0, 7.0]);
+        assert_eq!(rows(&contents[1]), [0.0, 3.0, 7.0]);
+        assert_eq!(rows(&contents[2]), [0.0, 4.0, 5.0, 6.0, 7.0]);
+
+        // 音素単位に展開した特徴量の幅は文の音素数と一致する
+        for (p, content) in sentences.into_iter().zip(&contents) {
+            let phone_len = p.phones.len();
+            let (bert, phones, _, _) = p.into_output(content, None).unwrap();
+            assert_eq!(bert.ncols(), phone_len);
+            assert_eq!(phones.len(), phone_len);
+        }
+    }
+
+    #[test]
+    fn slice_paragraph_content_rejects_length_mismatch() {
+        let paragraph_content = Array2::<f32>::zeros((5, 3));
+        assert!(slice_paragraph_content(&paragraph_content, &[2, 2]).is_err());
+    }
+}
@@ -10,3 +10,18 @@ where
         .for_each(|(r, s)| *r = s.clone());
     result
 }
+
+pub fn distribute_phone(n_phone: i32, n_word: i32) -> Vec<i32> {
+    let mut phones_per_word = vec![0; n_word as usize];
+
+    for _ in 0..n_phone {
+        let min_task = phones_per_word.iter().min().unwrap();
+        let min_index = phones_per_word
+            .iter()
+            .position(|&x| x == *min_task)
+            .unwrap();
+
+        phones_per_word[min_index] += 1;
+    }
+    phones_per_word
+}